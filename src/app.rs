@@ -1,17 +1,31 @@
 use leptos::prelude::*;
+use leptos::task::spawn_local;
 use leptos_meta::{provide_meta_context, MetaTags, Stylesheet, Title};
 use leptos_router::{
     components::{Route, Router, Routes},
+    hooks::use_query_map,
     path, StaticSegment,
 };
 use leptos_use::use_interval_fn;
 
 use crate::views::*;
 use crate::{
-    components::inventory::DragState,
-    entities::{tile::TileState, GameState, Tile},
+    components::{
+        away_summary::format_stacks, inventory::DragState, use_notifications, AwaySummaryDialog,
+        Notifications, ToastStack,
+    },
+    entities::{tile::TileState, AwaySummary, GameState, ItemDetails, Money, Tile},
+    exchange::claim_settlements,
+    persistence::{load_game, save_game, PlayerId},
+    sim::SimEvent,
+    world::{self, MAP_HEIGHT, MAP_WIDTH, WORLD_SEED},
 };
 
+const AUTOSAVE_INTERVAL_MS: u64 = 15_000;
+const SETTLEMENT_INTERVAL_MS: u64 = 5_000;
+/// Tile every new game starts with, already staffed and producing.
+const START_TILE: &str = "STR-1002";
+
 pub fn shell(options: LeptosOptions) -> impl IntoView {
     view! {
         <!DOCTYPE html>
@@ -34,55 +48,24 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
 pub fn ProvideGameState() -> impl IntoView {
     let mut game_state = GameState::new();
 
-    let mut tiles = Vec::new();
-    tiles.push(Tile {
-        id: "STR-1001",
-        description: "Test tile 1",
-        resources: &["Water: 60%", "Wood: 24%"],
-        price: 490.90,
-        is_owned: RwSignal::new(false),
-        row: 0,
-        col: 0,
-        tile_state: TileState::new(),
-        ..Default::default()
-    });
-    tiles.push(Tile {
-        id: "STR-1002",
-        description: "Test tile 2",
-        resources: &["Water: 60%", "Wood: 24%"],
-        price: 121.00,
-        is_owned: RwSignal::new(true),
-        row: 0,
-        col: 1,
-        tile_state: TileState::with_production(),
-        ..Default::default()
-    });
-    tiles.push(Tile {
-        id: "STR-1003",
-        description: "Test tile 3",
-        resources: &["Water: 60%", "Wood: 24%"],
-        price: 525.00,
-        is_owned: RwSignal::new(false),
-        row: 1,
-        col: 0,
-        tile_state: TileState::new(),
-        ..Default::default()
-    });
-    tiles.push(Tile {
-        id: "STR-1004",
-        description: "Test tile 4",
-        resources: &["Water: 60%", "Wood: 24%"],
-        price: 710.55,
-        is_owned: RwSignal::new(false),
-        row: 1,
-        col: 1,
-        tile_state: TileState::new(),
-        ..Default::default()
-    });
-
+    let mut tiles = world::generate(WORLD_SEED, MAP_WIDTH, MAP_HEIGHT);
+    if let Some(start) = tiles.iter_mut().find(|tile| tile.id == START_TILE) {
+        start.is_owned.set(true);
+        start.tile_state = TileState {
+            resources: start.tile_state.resources,
+            ..TileState::with_production()
+        };
+    }
     game_state.tiles = tiles;
 
     provide_context(game_state);
+    // `?player=<id>` plays as someone else, to trade between players locally.
+    let player_id = use_query_map()
+        .with_untracked(|query| query.get("player"))
+        .map(PlayerId)
+        .unwrap_or_default();
+    provide_context(player_id);
+    provide_context(Notifications::new());
     provide_context(DragState {
         dragging: RwSignal::new(None),
         mouse_pos: RwSignal::new((0, 0)),
@@ -92,34 +75,211 @@ pub fn ProvideGameState() -> impl IntoView {
 #[component]
 pub fn ProductionTicker() -> impl IntoView {
     let game_state = use_context::<GameState>().expect("GameState context");
+    let notifications = use_notifications();
 
     use_interval_fn(
         move || {
-            let now = chrono::Utc::now();
-
-            for tile in &game_state.tiles {
-                tile.tile_state.production_queue.update(|slots| {
-                    for slot in slots.iter_mut() {
-                        if let Some(completion) = slot.next_completion {
-                            if now >= completion {
-                                if let Some(recipe) = &slot.recipe {
-                                    let inventory = tile.tile_state.inventory;
-                                    inventory.update(|inv| {
-                                        inv.add_item(recipe.item_id, recipe.batch_size);
-                                    });
-                                    slot.started_at = Some(now);
-                                    slot.next_completion = Some(now + recipe.batch_duration);
+            for event in game_state.advance(chrono::Utc::now()) {
+                match event {
+                    SimEvent::Produced { tile_id, result } => notifications.info(format!(
+                        "{}: {}",
+                        tile_id,
+                        format_stacks(&result.produced, '+')
+                    )),
+                    SimEvent::Charged(charge) if !charge.is_paid_in_full() => {
+                        notifications.error(format!(
+                            "{}: could not pay {} x{}, short {}.",
+                            charge.tile_id,
+                            charge.kind.label(),
+                            charge.count,
+                            charge.due - charge.paid
+                        ))
+                    }
+                    SimEvent::Charged(_) => {}
+                    SimEvent::WorkersQuit {
+                        tile_id,
+                        worker_type,
+                        count,
+                    } => notifications.error(format!(
+                        "{}: {} unpaid {}s quit.",
+                        tile_id,
+                        count,
+                        worker_type.details().name
+                    )),
+                    SimEvent::BuildingStruck {
+                        tile_id,
+                        building_id,
+                    } => notifications.error(format!(
+                        "{}: workers of building {} are on strike, it was idled.",
+                        tile_id,
+                        building_id.short()
+                    )),
+                    SimEvent::Arrived {
+                        vehicle_id,
+                        tile_id,
+                        unloaded,
+                    } if unloaded.is_empty() => notifications.info(format!(
+                        "{}: vehicle {} arrived.",
+                        tile_id,
+                        vehicle_id.short()
+                    )),
+                    SimEvent::Arrived {
+                        vehicle_id,
+                        tile_id,
+                        unloaded,
+                    } => notifications.info(format!(
+                        "{}: vehicle {} arrived and unloaded {}",
+                        tile_id,
+                        vehicle_id.short(),
+                        format_stacks(&unloaded, '+')
+                    )),
+                    SimEvent::Depleted { tile_id, resource } => notifications.error(format!(
+                        "{}: the {} is used up, extraction has stopped.",
+                        tile_id,
+                        resource.details().name.to_lowercase()
+                    )),
+                }
+            }
+        },
+        1000,
+    );
+
+    view! {}
+}
+
+/// Restores the saved game once hydrated, then periodically writes it back.
+#[component]
+pub fn PersistGameState() -> impl IntoView {
+    let game_state = use_context::<GameState>().expect("GameState context");
+    let player_id = use_context::<PlayerId>().expect("PlayerId context");
+    let notifications = use_notifications();
+    let loaded = RwSignal::new(false);
+    let away_summary = RwSignal::new(None);
+
+    Effect::new({
+        let game_state = game_state.clone();
+        let player_id = player_id.clone();
+        move |_| {
+            let game_state = game_state.clone();
+            let player_id = player_id.0.clone();
+            spawn_local(async move {
+                match load_game(player_id).await {
+                    Ok(Some(snapshot)) => {
+                        game_state.restore(&snapshot);
+                        let now = chrono::Utc::now();
+                        let mut tiles = Vec::new();
+                        let mut upkeep_paid = Money::ZERO;
+                        for event in game_state.advance(now) {
+                            match event {
+                                SimEvent::Produced { tile_id, result } => {
+                                    tiles.push((tile_id, result))
                                 }
+                                SimEvent::Charged(charge) => upkeep_paid += charge.paid,
+                                _ => {}
                             }
                         }
+                        if !tiles.is_empty() || upkeep_paid > Money::ZERO {
+                            away_summary.set(Some(AwaySummary {
+                                elapsed: now - snapshot.saved_at,
+                                tiles,
+                                upkeep_paid,
+                            }));
+                        }
                     }
-                });
+                    Ok(None) => {}
+                    Err(err) => notifications.error(format!("Failed to load save: {}", err)),
+                }
+                loaded.set(true);
+            });
+        }
+    });
+
+    let save = {
+        let game_state = game_state.clone();
+        let player_id = player_id.clone();
+        move || {
+            let snapshot = game_state.snapshot();
+            let player_id = player_id.0.clone();
+            spawn_local(async move {
+                if let Err(err) = save_game(player_id, snapshot).await {
+                    notifications.error(format!("Failed to save game: {}", err));
+                }
+            });
+        }
+    };
+
+    use_interval_fn(
+        {
+            let save = save.clone();
+            move || {
+                // Saving before the load finished would overwrite progress with the defaults.
+                if loaded.get_untracked() {
+                    save();
+                }
             }
         },
-        1000,
+        AUTOSAVE_INTERVAL_MS,
     );
 
-    view! {}
+    // Collects what the exchange owes and saves right away, since the exchange
+    // forgets a settlement once it was claimed.
+    use_interval_fn(
+        move || {
+            if !loaded.get_untracked() {
+                return;
+            }
+            let space = game_state
+                .tiles
+                .iter()
+                .filter(|tile| tile.is_owned.get_untracked())
+                .flat_map(Tile::free_space)
+                .collect();
+            let player_id = player_id.0.clone();
+            let game_state = game_state.clone();
+            let save = save.clone();
+            spawn_local(async move {
+                let settlements = match claim_settlements(player_id, space).await {
+                    Ok(settlements) => settlements,
+                    Err(err) => {
+                        notifications.error(format!("Failed to reach the exchange: {}", err));
+                        return;
+                    }
+                };
+                for settlement in &settlements {
+                    let lost = settlement.apply(&game_state);
+                    let name = ItemDetails::get(settlement.item_id)
+                        .map_or(settlement.item_id.0, |item| item.name);
+                    if settlement.quantity > 0 {
+                        notifications.success(format!(
+                            "Exchange: {} {} delivered to {}.",
+                            settlement.quantity, name, settlement.tile_id
+                        ));
+                    }
+                    if settlement.cash != Money::ZERO {
+                        notifications.success(format!(
+                            "Exchange: received {} for {}.",
+                            settlement.cash, name
+                        ));
+                    }
+                    if lost > 0 {
+                        notifications.error(format!(
+                            "Exchange: {} {} did not fit in {}.",
+                            lost, name, settlement.tile_id
+                        ));
+                    }
+                }
+                if !settlements.is_empty() {
+                    save();
+                }
+            });
+        },
+        SETTLEMENT_INTERVAL_MS,
+    );
+
+    view! {
+        <AwaySummaryDialog summary=away_summary />
+        <ToastStack />
+    }
 }
 
 #[component]
@@ -139,10 +299,14 @@ pub fn App() -> impl IntoView {
         <Router>
             <main>
                 <ProvideGameState />
+                <PersistGameState />
                 <ProductionTicker />
                 <Routes fallback=|| "Page not found.".into_view()>
                     <Route path=StaticSegment("") view=HomePage />
                     <Route path=StaticSegment("/forestry") view=ForestryPage />
+                    <Route path=StaticSegment("/market") view=MarketPage />
+                    <Route path=StaticSegment("/exchange") view=ExchangePage />
+                    <Route path=StaticSegment("/shipments") view=ShipmentsPage />
                     <Route path=StaticSegment("/tile-map") view=TileMapPage />
                     <Route path=path!("/tile/:id") view=TilePage />
                 </Routes>
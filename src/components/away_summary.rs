@@ -0,0 +1,72 @@
+use leptos::prelude::*;
+
+use crate::entities::{AwaySummary, ItemId};
+
+pub(crate) fn format_elapsed(elapsed: chrono::Duration) -> String {
+    let minutes = elapsed.num_minutes();
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => format!("{}s", elapsed.num_seconds()),
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
+
+pub(crate) fn format_stacks(stacks: &[(ItemId, u64)], sign: char) -> String {
+    stacks
+        .iter()
+        .map(|(id, quantity)| format!("{sign}{quantity} {}", id.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[component]
+pub fn AwaySummaryDialog(summary: RwSignal<Option<AwaySummary>>) -> impl IntoView {
+    view! {
+        <Show when=move || summary.get().is_some()>
+            {move || {
+                let AwaySummary { elapsed, tiles, upkeep_paid } = summary.get().unwrap();
+                view! {
+                    <div class="fixed inset-0 z-30 flex items-center justify-center bg-black/60">
+                        <div class="flex flex-col gap-4 p-6 w-full max-w-lg bg-secondary-bg border border-primary-border">
+                            <div class="flex justify-between items-baseline">
+                                <div class="text-2xl font-semibold">"WHILE YOU WERE AWAY"</div>
+                                <div class="text-secondary-text">{format_elapsed(elapsed)}</div>
+                            </div>
+                            <ul class="flex flex-col gap-2">
+                                {tiles
+                                    .into_iter()
+                                    .map(|(tile_id, result)| {
+                                        view! {
+                                            <li class="flex justify-between">
+                                                <span class="font-semibold">{tile_id}</span>
+                                                <span class="text-sm text-secondary-text">
+                                                    {format!("{} batches", result.batches)}
+                                                </span>
+                                                <span class="text-highlight-dim">
+                                                    {format_stacks(&result.produced, '+')}
+                                                </span>
+                                                <span class="text-destructive">
+                                                    {format_stacks(&result.consumed, '-')}
+                                                </span>
+                                            </li>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </ul>
+                            <div class="flex justify-between">
+                                <span class="font-semibold">"Wages and upkeep"</span>
+                                <span class="text-destructive">{format!("-{}", upkeep_paid)}</span>
+                            </div>
+                            <button
+                                on:click=move |_| summary.set(None)
+                                class="self-end border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer py-2 px-4"
+                            >
+                                "DISMISS"
+                            </button>
+                        </div>
+                    </div>
+                }
+            }}
+        </Show>
+    }
+}
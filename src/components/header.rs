@@ -1,6 +1,7 @@
 use leptos::prelude::*;
 use leptos_router::hooks::use_params;
 
+use crate::components::NotificationHistory;
 use crate::entities::GameState;
 use crate::views::tile::TileParams;
 
@@ -78,8 +79,9 @@ pub fn Header() -> impl IntoView {
                 <div class="flex flex-1 justify-center text-primary-text text-3xl">
                     <span>{id}</span>
                 </div>
-                <div class="flex flex-1 justify-end text-primary-text font-semibold text-3xl">
-                    {move || format!("{:.2}", context.cash.get())}
+                <div class="flex flex-1 justify-end items-center gap-4 text-primary-text font-semibold text-3xl">
+                    <NotificationHistory />
+                    {move || context.cash.get().to_string()}
                 </div>
             </div>
         </nav>
@@ -1,10 +1,16 @@
 pub mod accordion;
+pub mod away_summary;
 pub mod button;
 pub mod header;
 pub mod inventory;
+pub mod notifications;
 pub mod tabs;
 
 pub use accordion::{Accordion, AccordionContent, AccordionItem, AccordionTrigger, AccordionType};
+pub use away_summary::AwaySummaryDialog;
 pub use header::Header;
 pub use inventory::{DragState, DraggableItemOverlay, InventoryContainer};
+pub use notifications::{
+    use_notifications, NotificationHistory, Notifications, ToastKind, ToastStack,
+};
 pub use tabs::{Tabs, TabsContent, TabsList, TabsTrigger};
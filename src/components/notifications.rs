@@ -0,0 +1,176 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use leptos::prelude::*;
+
+/// How long a toast stays on screen before dismissing itself.
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+/// Number of past notifications kept for the history panel.
+const HISTORY_LEN: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastKind {
+    Success,
+    Error,
+    Info,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    pub id: u64,
+    pub kind: ToastKind,
+    pub message: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Queue of on-screen toasts and the history behind them.
+#[derive(Copy, Clone)]
+pub struct Notifications {
+    pub toasts: RwSignal<Vec<Toast>>,
+    pub history: RwSignal<Vec<Toast>>,
+    next_id: StoredValue<u64>,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self {
+            toasts: RwSignal::new(Vec::new()),
+            history: RwSignal::new(Vec::new()),
+            next_id: StoredValue::new(0),
+        }
+    }
+
+    pub fn push(&self, kind: ToastKind, message: impl Into<String>) {
+        let id = self.next_id.get_value();
+        self.next_id.set_value(id + 1);
+        let toast = Toast {
+            id,
+            kind,
+            message: message.into(),
+            at: Utc::now(),
+        };
+
+        self.history.update(|history| {
+            history.push(toast.clone());
+            let excess = history.len().saturating_sub(HISTORY_LEN);
+            history.drain(..excess);
+        });
+        self.toasts.update(|toasts| toasts.push(toast));
+
+        let this = *self;
+        set_timeout(move || this.dismiss(id), TOAST_DURATION);
+    }
+
+    pub fn success(&self, message: impl Into<String>) {
+        self.push(ToastKind::Success, message);
+    }
+
+    pub fn error(&self, err: impl Display) {
+        self.push(ToastKind::Error, err.to_string());
+    }
+
+    pub fn info(&self, message: impl Into<String>) {
+        self.push(ToastKind::Info, message);
+    }
+
+    /// Shows the error of a failed action, or `success` built from its result.
+    pub fn report<T, E: Display>(&self, result: Result<T, E>, success: impl FnOnce(T) -> String) {
+        match result {
+            Ok(value) => self.success(success(value)),
+            Err(err) => self.error(err),
+        }
+    }
+
+    pub fn dismiss(&self, id: u64) {
+        self.toasts
+            .update(|toasts| toasts.retain(|toast| toast.id != id));
+    }
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn use_notifications() -> Notifications {
+    use_context::<Notifications>().expect("Notifications context not found.")
+}
+
+fn kind_class(kind: ToastKind) -> &'static str {
+    match kind {
+        ToastKind::Success => "border-highlight-dim text-highlight-dim",
+        ToastKind::Error => "border-destructive text-destructive",
+        ToastKind::Info => "border-primary-border text-primary-text",
+    }
+}
+
+/// Stack of active toasts in the bottom right corner.
+#[component]
+pub fn ToastStack() -> impl IntoView {
+    let notifications = use_notifications();
+
+    view! {
+        <div class="fixed bottom-4 right-4 z-40 flex flex-col gap-2 w-80">
+            <For
+                each=move || notifications.toasts.get()
+                key=|toast| toast.id
+                children=move |toast: Toast| {
+                    let id = toast.id;
+                    view! {
+                        <div
+                            on:click=move |_| notifications.dismiss(id)
+                            class=format!(
+                                "px-4 py-2 border bg-secondary-bg hover:cursor-pointer {}",
+                                kind_class(toast.kind),
+                            )
+                        >
+                            {toast.message}
+                        </div>
+                    }
+                }
+            />
+        </div>
+    }
+}
+
+/// Button opening a scrollable list of past notifications, newest first.
+#[component]
+pub fn NotificationHistory() -> impl IntoView {
+    let notifications = use_notifications();
+    let open = RwSignal::new(false);
+
+    view! {
+        <div class="relative">
+            <button
+                on:click=move |_| open.update(|open| *open = !*open)
+                class="border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer py-1 px-3 text-base"
+            >
+                {move || format!("LOG ({})", notifications.history.with(Vec::len))}
+            </button>
+            <Show when=move || open.get()>
+                <ul class="absolute right-0 top-full mt-2 z-40 w-96 max-h-96 overflow-y-auto flex flex-col gap-1 p-2 bg-secondary-bg border border-primary-border text-sm font-normal">
+                    {move || {
+                        notifications
+                            .history
+                            .get()
+                            .into_iter()
+                            .rev()
+                            .map(|toast| {
+                                view! {
+                                    <li class=format!("flex gap-2 {}", kind_class(toast.kind))>
+                                        <span class="text-secondary-text">
+                                            {toast.at.format("%H:%M:%S").to_string()}
+                                        </span>
+                                        <span>{toast.message}</span>
+                                    </li>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </ul>
+            </Show>
+        </div>
+    }
+}
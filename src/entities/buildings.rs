@@ -29,3 +29,34 @@ impl Buildings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_owner;
+
+    #[test]
+    fn capacity_counts_only_matching_housing() {
+        let _owner = test_owner();
+        let buildings = Buildings::new();
+        buildings.housing.build(HousingType::Cheap, 3);
+        buildings.housing.build(HousingType::Fancy, 2);
+
+        assert_eq!(buildings.get_capacity(WorkerType::Basic), 30);
+        assert_eq!(buildings.get_capacity(WorkerType::Advanced), 0);
+        assert_eq!(buildings.get_capacity(WorkerType::Expert), 6);
+    }
+
+    #[test]
+    fn capacity_follows_demolition() {
+        let _owner = test_owner();
+        let buildings = Buildings::new();
+        buildings.housing.build(HousingType::Standard, 4);
+        buildings.housing.destroy(HousingType::Standard, 3).unwrap();
+
+        assert_eq!(
+            buildings.get_capacity(WorkerType::Advanced),
+            HousingType::Standard.details().accomodates.0
+        );
+    }
+}
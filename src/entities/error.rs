@@ -0,0 +1,222 @@
+use std::fmt;
+
+use crate::entities::{HousingType, ItemId, Money, ResourceKind, WorkerType};
+
+/// Why a game action was refused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameError {
+    InsufficientFunds {
+        need: Money,
+        have: Money,
+    },
+    InsufficientLand {
+        need: u64,
+        available: u64,
+    },
+    /// Freeing more land than is in use, only happens on bad bookkeeping.
+    LandNotInUse {
+        freeing: u64,
+        used: u64,
+    },
+    NoHousingCapacity {
+        worker_type: WorkerType,
+        requested: u64,
+        space: u64,
+    },
+    NotEnoughHousing {
+        housing_type: HousingType,
+        requested: u64,
+        owned: u64,
+    },
+    WorkersHomeless {
+        worker_type: WorkerType,
+        homeless: u64,
+    },
+    NotEnoughWorkers {
+        worker_type: WorkerType,
+        requested: u64,
+        hired: u64,
+    },
+    WorkersUnavailable {
+        worker_type: WorkerType,
+        need: u64,
+        available: u64,
+    },
+    WorkersAssigned {
+        worker_type: WorkerType,
+        requested: u64,
+        unassigned: u64,
+    },
+    WorkersNotAssigned {
+        worker_type: WorkerType,
+        requested: u64,
+        assigned: u64,
+    },
+    BuildingNotFound,
+    MaxLevel {
+        level: u32,
+    },
+    InventoryFull {
+        item_id: ItemId,
+        requested: u64,
+        fits: u64,
+    },
+    UnknownItem(ItemId),
+    NotEnoughItems {
+        item_id: ItemId,
+        requested: u64,
+        stored: u64,
+    },
+    NotTraded(ItemId),
+    InvalidAmount,
+    /// An exchange order without a quantity or a price.
+    EmptyOrder,
+    OrderNotFound,
+    /// The server has no save to take an order's escrow from.
+    NoSavedGame,
+    TileNotOwned,
+    SameTile,
+    /// No passable terrain connects the two tiles.
+    NoRoute,
+    VehicleNotFound,
+    VehicleEnRoute,
+    /// Building an extractor on a tile without the resource it extracts.
+    NoDeposit(ResourceKind),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InsufficientFunds { need, have } => {
+                write!(f, "Insufficient funds. Need {}, have {}.", need, have)
+            }
+            Self::InsufficientLand { need, available } => {
+                write!(
+                    f,
+                    "Not enough land. Need: {}, available: {}.",
+                    need, available
+                )
+            }
+            Self::LandNotInUse { freeing, used } => write!(
+                f,
+                "Trying to free more land than is used. Used: {}, freeing: {}.",
+                used, freeing
+            ),
+            Self::NoHousingCapacity {
+                worker_type,
+                requested,
+                space,
+            } => write!(
+                f,
+                "Cannot hire {} {}s, only space for {}.",
+                requested,
+                worker_type.details().name,
+                space
+            ),
+            Self::NotEnoughHousing {
+                housing_type,
+                requested,
+                owned,
+            } => write!(
+                f,
+                "Cannot demolish {} {}, only have {}.",
+                requested,
+                housing_type.details().name,
+                owned
+            ),
+            Self::WorkersHomeless {
+                worker_type,
+                homeless,
+            } => write!(
+                f,
+                "Demolishing would leave {} assigned {}s homeless.",
+                homeless,
+                worker_type.details().name
+            ),
+            Self::NotEnoughWorkers {
+                worker_type,
+                requested,
+                hired,
+            } => write!(
+                f,
+                "Cannot fire {} {}s, only {} hired.",
+                requested,
+                worker_type.details().name,
+                hired
+            ),
+            Self::WorkersUnavailable {
+                worker_type,
+                need,
+                available,
+            } => write!(
+                f,
+                "Not enough {}s. Need {}, have {}.",
+                worker_type.details().name,
+                need,
+                available
+            ),
+            Self::WorkersAssigned {
+                worker_type,
+                requested,
+                unassigned,
+            } => write!(
+                f,
+                "Cannot fire {} {}s, only {} are unassigned. Idle or demolish buildings first.",
+                requested,
+                worker_type.details().name,
+                unassigned
+            ),
+            Self::WorkersNotAssigned {
+                worker_type,
+                requested,
+                assigned,
+            } => write!(
+                f,
+                "Cannot unassign {} {}s, only {} assigned.",
+                requested,
+                worker_type.details().name,
+                assigned
+            ),
+            Self::BuildingNotFound => write!(f, "Building not found."),
+            Self::MaxLevel { level } => write!(f, "Building is already at max level {}.", level),
+            Self::InventoryFull {
+                item_id,
+                requested,
+                fits,
+            } => write!(
+                f,
+                "Inventory is full. Cannot store {} {}, only {} fit.",
+                requested, item_id.0, fits
+            ),
+            Self::UnknownItem(item_id) => write!(f, "Unknown item `{}`.", item_id.0),
+            Self::NotEnoughItems {
+                item_id,
+                requested,
+                stored,
+            } => write!(
+                f,
+                "Not enough {}. Requested {}, stored {}.",
+                item_id.0, requested, stored
+            ),
+            Self::NotTraded(item_id) => write!(f, "{} is not traded on the market.", item_id.0),
+            Self::InvalidAmount => write!(f, "Not a valid amount of money."),
+            Self::EmptyOrder => write!(f, "Orders need a quantity and a price above zero."),
+            Self::OrderNotFound => write!(f, "Order not found."),
+            Self::NoSavedGame => write!(f, "No saved game to trade from."),
+            Self::TileNotOwned => write!(f, "You do not own this tile."),
+            Self::SameTile => write!(f, "Source and destination are the same tile."),
+            Self::NoRoute => write!(f, "No route leads to that tile."),
+            Self::VehicleNotFound => write!(f, "Vehicle not found."),
+            Self::VehicleEnRoute => write!(f, "The vehicle is on the road."),
+            Self::NoDeposit(resource) => {
+                write!(
+                    f,
+                    "There is no {} on this tile.",
+                    resource.details().name.to_lowercase()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
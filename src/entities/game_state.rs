@@ -1,18 +1,96 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
 use leptos::prelude::*;
 
-use crate::entities::Tile;
+use crate::entities::payroll::LEDGER_LEN;
+use crate::entities::{CatchUp, Charge, Market, Money, Tile, Vehicle};
+use crate::sim::{self, SimEvent, SimState};
+use crate::world::hex::{self, Path};
 
 #[derive(Clone, Debug)]
 pub struct GameState {
-    pub cash: RwSignal<f64>,
+    pub cash: RwSignal<Money>,
     pub tiles: Vec<Tile<'static>>,
+    /// Most recent wage and maintenance charges, oldest first.
+    pub ledger: RwSignal<Vec<Charge>>,
+    pub last_payroll: RwSignal<DateTime<Utc>>,
+    /// Time `advance` last ran the rules up to.
+    pub clock: RwSignal<DateTime<Utc>>,
+    pub market: RwSignal<Market>,
+    /// Vehicles of the player, parked or on the road.
+    pub vehicles: RwSignal<Vec<Vehicle>>,
+}
+
+/// Production credited while the game was closed, per tile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AwaySummary {
+    pub elapsed: Duration,
+    pub tiles: Vec<(String, CatchUp)>,
+    /// Wages and maintenance paid over the same time.
+    pub upkeep_paid: Money,
 }
 
 impl GameState {
     pub fn new() -> Self {
         Self {
-            cash: RwSignal::new(10000.),
+            cash: RwSignal::new(Money::dollars(10000)),
             tiles: Vec::new(),
+            ledger: RwSignal::new(Vec::new()),
+            last_payroll: RwSignal::new(Utc::now()),
+            clock: RwSignal::new(Utc::now()),
+            market: RwSignal::new(Market::default()),
+            vehicles: RwSignal::new(Vec::new()),
         }
     }
+
+    /// Steps the rules in `sim` from `clock` to `now`, writes the result back
+    /// into the signals and records the charges in the ledger. Only tiles the
+    /// step changed are written back.
+    pub fn advance(&self, now: DateTime<Utc>) -> Vec<SimEvent> {
+        let before = self.snapshot_at(self.clock.get_untracked());
+        let mut sim = SimState::from_snapshot(&before);
+        let dt = now - sim.now;
+        let events = sim::step(&mut sim, dt);
+        let mut after = sim.snapshot();
+        let before_by_id: HashMap<_, _> = before
+            .tiles
+            .iter()
+            .map(|tile| (tile.id.as_str(), tile))
+            .collect();
+        after
+            .tiles
+            .retain(|tile| before_by_id.get(tile.id.as_str()) != Some(&tile));
+        self.restore(&after);
+
+        let charges: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                SimEvent::Charged(charge) => Some(charge.clone()),
+                _ => None,
+            })
+            .collect();
+        if !charges.is_empty() {
+            self.ledger.update(|ledger| {
+                ledger.extend(charges);
+                let excess = ledger.len().saturating_sub(LEDGER_LEN);
+                ledger.drain(..excess);
+            });
+        }
+        events
+    }
+
+    /// Cheapest route over the map by terrain movement cost.
+    pub fn route(&self, from: &Tile, to: &Tile) -> Option<Path> {
+        let by_offset: HashMap<_, _> = self
+            .tiles
+            .iter()
+            .map(|tile| ((tile.row as i32, tile.col as i32), tile))
+            .collect();
+        hex::find_path(from.hex(), to.hex(), |hex| {
+            by_offset
+                .get(&hex.to_offset())
+                .map(|tile| tile.terrain.details().move_cost)
+        })
+    }
 }
@@ -1,5 +1,7 @@
 use leptos::prelude::*;
 
+use crate::entities::{GameError, Money, WorkerType};
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum HousingType {
     Cheap,
@@ -12,28 +14,40 @@ impl HousingType {
         vec![Self::Cheap, Self::Standard, Self::Fancy]
     }
 
+    /// The kind of worker this housing accommodates.
+    pub fn worker_type(&self) -> WorkerType {
+        match self {
+            HousingType::Cheap => WorkerType::Basic,
+            HousingType::Standard => WorkerType::Advanced,
+            HousingType::Fancy => WorkerType::Expert,
+        }
+    }
+
     pub fn details(&self) -> HousingDetails {
         match self {
             HousingType::Cheap => HousingDetails {
                 name: "Cheap Housing",
                 description: "The most basic shack.",
-                cost: 500.0,
+                cost: Money::dollars(500),
                 accomodates: Accomodation(10, "Basic"),
                 land_used: 10,
+                upkeep: Money::from_cents(25),
             },
             HousingType::Standard => HousingDetails {
                 name: "Standard Housing",
                 description: "Normal housing with some comforts.",
-                cost: 750.0,
+                cost: Money::dollars(750),
                 accomodates: Accomodation(5, "Advanced"),
                 land_used: 20,
+                upkeep: Money::from_cents(50),
             },
             HousingType::Fancy => HousingDetails {
                 name: "Fancy Housing",
                 description: "A fancy home fit for a king.",
-                cost: 1250.0,
+                cost: Money::dollars(1250),
                 accomodates: Accomodation(3, "Expert"),
                 land_used: 30,
+                upkeep: Money::from_cents(100),
             },
         }
     }
@@ -63,38 +77,27 @@ impl Housing {
         }
     }
 
-    pub fn destroy(&self, housing_type: HousingType, amount: u64) -> Result<(), String> {
+    pub fn count(&self, housing_type: HousingType) -> u64 {
         match housing_type {
-            HousingType::Cheap => {
-                if self.cheap.get() < amount {
-                    return Err(format!(
-                        "Cannot destroy {} cheap houses, only have {}.",
-                        amount,
-                        self.cheap.get()
-                    ));
-                }
-                self.cheap.update(|h| *h -= 1);
-            }
-            HousingType::Standard => {
-                if self.standard.get() < amount {
-                    return Err(format!(
-                        "Cannot destroy {} standard houses, only have {}.",
-                        amount,
-                        self.standard.get()
-                    ));
-                }
-                self.standard.update(|h| *h -= 1);
-            }
-            HousingType::Fancy => {
-                if self.fancy.get() < amount {
-                    return Err(format!(
-                        "Cannot destroy {} fancy houses, only have {}.",
-                        amount,
-                        self.fancy.get()
-                    ));
-                }
-                self.fancy.update(|h| *h -= 1);
-            }
+            HousingType::Cheap => self.cheap.get(),
+            HousingType::Standard => self.standard.get(),
+            HousingType::Fancy => self.fancy.get(),
+        }
+    }
+
+    pub fn destroy(&self, housing_type: HousingType, amount: u64) -> Result<(), GameError> {
+        let owned = self.count(housing_type);
+        if owned < amount {
+            return Err(GameError::NotEnoughHousing {
+                housing_type,
+                requested: amount,
+                owned,
+            });
+        }
+        match housing_type {
+            HousingType::Cheap => self.cheap.update(|h| *h -= amount),
+            HousingType::Standard => self.standard.update(|h| *h -= amount),
+            HousingType::Fancy => self.fancy.update(|h| *h -= amount),
         }
         Ok(())
     }
@@ -105,7 +108,59 @@ pub struct Accomodation(pub u64, pub &'static str);
 pub struct HousingDetails {
     pub name: &'static str,
     pub description: &'static str,
-    pub cost: f64,
+    pub cost: Money,
     pub accomodates: Accomodation,
     pub land_used: u64,
+    /// Maintenance charged per house every `PAYROLL_PERIOD`.
+    pub upkeep: Money,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_owner;
+
+    #[test]
+    fn destroy_removes_the_requested_amount() {
+        let _owner = test_owner();
+        let housing = Housing::new();
+        housing.build(HousingType::Standard, 5);
+
+        housing.destroy(HousingType::Standard, 3).unwrap();
+        assert_eq!(housing.count(HousingType::Standard), 2);
+        housing.destroy(HousingType::Standard, 2).unwrap();
+        assert_eq!(housing.count(HousingType::Standard), 0);
+    }
+
+    #[test]
+    fn destroying_more_than_owned_changes_nothing() {
+        let _owner = test_owner();
+        let housing = Housing::new();
+        housing.build(HousingType::Fancy, 2);
+
+        let err = housing.destroy(HousingType::Fancy, 3).unwrap_err();
+        assert_eq!(
+            err,
+            GameError::NotEnoughHousing {
+                housing_type: HousingType::Fancy,
+                requested: 3,
+                owned: 2,
+            }
+        );
+        assert_eq!(housing.count(HousingType::Fancy), 2);
+    }
+
+    #[test]
+    fn destroy_only_touches_its_own_type() {
+        let _owner = test_owner();
+        let housing = Housing::new();
+        for housing_type in HousingType::all() {
+            housing.build(housing_type, 4);
+        }
+
+        housing.destroy(HousingType::Cheap, 4).unwrap();
+        assert_eq!(housing.count(HousingType::Cheap), 0);
+        assert_eq!(housing.count(HousingType::Standard), 4);
+        assert_eq!(housing.count(HousingType::Fancy), 4);
+    }
 }
@@ -1,6 +1,9 @@
 use leptos::prelude::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 
+use crate::entities::GameError;
+
 #[derive(Copy, Clone, Debug, PartialEq, Default, PartialOrd, Eq, Ord, Hash)]
 pub struct ItemId(pub &'static str);
 
@@ -11,6 +14,24 @@ impl IntoRender for ItemId {
     }
 }
 
+impl Serialize for ItemId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemId {
+    /// Resolves the id against `ITEMS` so the `&'static str` is preserved.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        ItemDetails::all()
+            .iter()
+            .map(|item| item.id)
+            .find(|item_id| item_id.0 == id)
+            .ok_or_else(|| de::Error::custom(format!("unknown item id `{id}`")))
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Default)]
 pub struct ItemStack {
     pub id: ItemId,
@@ -61,15 +82,30 @@ impl Inventory {
     }
 
     pub fn empty_weight(&self) -> u64 {
-        self.max_weight.get() - self.weight.get()
+        self.max_weight.get().saturating_sub(self.weight.get())
     }
 
     pub fn empty_volume(&self) -> u64 {
-        self.max_volume.get() - self.volume.get()
+        self.max_volume.get().saturating_sub(self.volume.get())
     }
 
+    /// Quantity of `item_id` currently stored.
+    pub fn quantity(&self, item_id: ItemId) -> u64 {
+        self.items.with(|items| {
+            items
+                .iter()
+                .find(|i| i.id == item_id)
+                .map(|i| i.quantity.get())
+                .unwrap_or(0)
+        })
+    }
+
+    /// How many more of `item_id` fit by both weight and volume, zero for
+    /// unknown items.
     pub fn fits_max_items(&self, item_id: ItemId) -> u64 {
-        let item_details = ItemDetails::get(item_id).unwrap();
+        let Some(item_details) = ItemDetails::get(item_id) else {
+            return 0;
+        };
 
         u64::min(
             self.empty_volume() / item_details.volume,
@@ -77,11 +113,17 @@ impl Inventory {
         )
     }
 
-    pub fn add_item(&mut self, item_id: ItemId, quantity: u64) {
-        let item_details = ItemDetails::get(item_id).unwrap();
+    /// Stores as many of `quantity` as fit, returning how many were added.
+    pub fn add_item(&mut self, item_id: ItemId, quantity: u64) -> u64 {
+        let Some(item_details) = ItemDetails::get(item_id) else {
+            return 0;
+        };
 
         let max_qty_fits = self.fits_max_items(item_id);
         let moved_qty = u64::min(max_qty_fits, quantity);
+        if moved_qty == 0 {
+            return 0;
+        }
 
         self.items.update(move |items| {
             if let Some(item) = items.iter().find(|i| i.id == item_id) {
@@ -98,27 +140,43 @@ impl Inventory {
             .update(|v| *v += item_details.volume * moved_qty);
         self.weight
             .update(|w| *w += item_details.weight * moved_qty);
+        moved_qty
     }
 
-    pub fn remove_item(&mut self, item_id: ItemId, quantity: u64) {
-        let item_details = ItemDetails::get(item_id).unwrap();
+    /// Removes up to `quantity` of `item_id`, returning how many were actually
+    /// removed. Weight and volume only drop by what left the stack.
+    pub fn remove_item(&mut self, item_id: ItemId, quantity: u64) -> Result<u64, GameError> {
+        let item_details = ItemDetails::get(item_id).ok_or(GameError::UnknownItem(item_id))?;
+        let Some(stack) = self
+            .items
+            .with_untracked(|items| items.iter().find(|i| i.id == item_id).copied())
+        else {
+            return Ok(0);
+        };
 
-        self.items.update(|items| {
-            if let Some(item) = items.iter().find(|i| i.id == item_id) {
-                item.quantity
-                    .update(|qty| *qty = qty.saturating_sub(quantity));
-                if item.quantity.get() <= 0 {
-                    if let Some(pos) = items.iter().position(|i| i.id == item_id) {
-                        items.remove(pos);
-                    }
-                }
-            } else {
-                panic!("Something went wrong when removing items from inventory...");
-            }
-        });
+        let removed = stack.quantity.get_untracked().min(quantity);
+        stack.quantity.update(|qty| *qty -= removed);
+        if stack.quantity.get_untracked() == 0 {
+            self.items.update(|items| items.retain(|i| i.id != item_id));
+        }
 
-        self.volume.update(|v| *v -= item_details.volume * quantity);
-        self.weight.update(|w| *w -= item_details.weight * quantity);
+        self.volume.update(|v| *v -= item_details.volume * removed);
+        self.weight.update(|w| *w -= item_details.weight * removed);
+        Ok(removed)
+    }
+
+    /// Moves up to `quantity` of `item_id` into `destination`, limited by what is
+    /// stored here and what fits there. Returns how many were moved; nothing is
+    /// lost when the destination fills up.
+    pub fn transfer_to(
+        &mut self,
+        destination: &mut Inventory,
+        item_id: ItemId,
+        quantity: u64,
+    ) -> Result<u64, GameError> {
+        let available = self.quantity(item_id).min(quantity);
+        let moved = destination.add_item(item_id, available);
+        self.remove_item(item_id, moved)
     }
 }
 
@@ -179,3 +237,168 @@ const ITEMS: &[ItemDetails] = &[
         volume: 8_100,
     },
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_owner;
+    use crate::sim::{Stock, Storage};
+    use proptest::prelude::*;
+
+    fn inventory(max_weight: u64, max_volume: u64) -> Inventory {
+        let inventory = Inventory::new();
+        inventory.max_weight.set(max_weight);
+        inventory.max_volume.set(max_volume);
+        inventory
+    }
+
+    /// Weight and volume recomputed from the stacks.
+    fn stacked(inventory: &Inventory) -> (u64, u64) {
+        inventory.items.with(|items| {
+            items.iter().fold((0, 0), |(weight, volume), stack| {
+                let details = ItemDetails::get(stack.id).unwrap();
+                let quantity = stack.quantity.get();
+                (
+                    weight + details.weight * quantity,
+                    volume + details.volume * quantity,
+                )
+            })
+        })
+    }
+
+    #[test]
+    fn add_item_stops_at_the_tightest_limit() {
+        let _owner = test_owner();
+        // Chairs are light but bulky, so volume runs out first.
+        let mut inventory = inventory(100_000, 30_000);
+        assert_eq!(inventory.fits_max_items(ItemId("CHR")), 10);
+
+        inventory.add_item(ItemId("CHR"), 25);
+        assert_eq!(inventory.quantity(ItemId("CHR")), 10);
+        assert_eq!(inventory.volume.get(), 30_000);
+        assert_eq!(inventory.weight.get(), 9_000);
+        assert_eq!(inventory.fits_max_items(ItemId("CHR")), 0);
+    }
+
+    #[test]
+    fn add_item_merges_into_the_existing_stack() {
+        let _owner = test_owner();
+        let mut inventory = inventory(500_000, 500_000);
+        inventory.add_item(ItemId("LOG"), 3);
+        inventory.add_item(ItemId("LOG"), 4);
+
+        assert_eq!(inventory.items.with(Vec::len), 1);
+        assert_eq!(inventory.quantity(ItemId("LOG")), 7);
+    }
+
+    #[test]
+    fn removing_a_whole_stack_drops_it() {
+        let _owner = test_owner();
+        let mut inventory = inventory(500_000, 500_000);
+        inventory.add_item(ItemId("BRD"), 6);
+        assert_eq!(inventory.remove_item(ItemId("BRD"), 6), Ok(6));
+
+        assert!(inventory.items.with(Vec::is_empty));
+        assert_eq!((inventory.weight.get(), inventory.volume.get()), (0, 0));
+    }
+
+    #[test]
+    fn removing_more_than_stored_removes_what_is_there() {
+        let _owner = test_owner();
+        let mut inventory = inventory(500_000, 500_000);
+        inventory.add_item(ItemId("LOG"), 4);
+        inventory.add_item(ItemId("H2O"), 2);
+
+        assert_eq!(inventory.remove_item(ItemId("LOG"), 10), Ok(4));
+        assert_eq!(inventory.remove_item(ItemId("LOG"), 1), Ok(0));
+        assert_eq!(inventory.weight.get(), 4_000);
+        assert_eq!(inventory.volume.get(), 3_000);
+    }
+
+    #[test]
+    fn removing_an_unknown_item_is_an_error() {
+        let _owner = test_owner();
+        let mut inventory = inventory(500_000, 500_000);
+        assert_eq!(
+            inventory.remove_item(ItemId("NOPE"), 1),
+            Err(GameError::UnknownItem(ItemId("NOPE")))
+        );
+    }
+
+    #[test]
+    fn transfer_keeps_what_does_not_fit() {
+        let _owner = test_owner();
+        let mut source = inventory(500_000, 500_000);
+        let mut destination = inventory(500_000, 6_000);
+        source.add_item(ItemId("CHR"), 5);
+
+        assert_eq!(
+            source.transfer_to(&mut destination, ItemId("CHR"), 5),
+            Ok(2)
+        );
+        assert_eq!(source.quantity(ItemId("CHR")), 3);
+        assert_eq!(destination.quantity(ItemId("CHR")), 2);
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        Add(usize, u64),
+        Remove(usize, u64),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        let item = 0..ItemDetails::all().len();
+        prop_oneof![
+            (item.clone(), 0..200u64).prop_map(|(item, n)| Op::Add(item, n)),
+            (item, 0..200u64).prop_map(|(item, n)| Op::Remove(item, n)),
+        ]
+    }
+
+    proptest! {
+        /// Random add/remove sequences never exceed the limits, keep weight and
+        /// volume equal to what the stacks hold, and store exactly what was
+        /// accepted minus what was removed.
+        #[test]
+        fn add_and_remove_conserve_items(
+            max_weight in 0..300_000u64,
+            max_volume in 0..300_000u64,
+            ops in prop::collection::vec(op(), 0..60),
+        ) {
+            let _owner = test_owner();
+            let mut inventory = inventory(max_weight, max_volume);
+            let mut stock = Stock {
+                items: vec![],
+                max_volume,
+                max_weight,
+            };
+            for op in ops {
+                match op {
+                    Op::Add(item, n) => {
+                        let id = ItemDetails::all()[item].id;
+                        let before = inventory.quantity(id);
+                        let fits = inventory.fits_max_items(id);
+                        let added = inventory.add_item(id, n);
+                        prop_assert_eq!(added, n.min(fits));
+                        prop_assert_eq!(stock.add_item(id, n), added);
+                        prop_assert_eq!(inventory.quantity(id), before + added);
+                    }
+                    Op::Remove(item, n) => {
+                        let id = ItemDetails::all()[item].id;
+                        let before = inventory.quantity(id);
+                        let removed = inventory.remove_item(id, n).unwrap();
+                        prop_assert_eq!(removed, n.min(before));
+                        prop_assert_eq!(stock.remove_item(id, n), Ok(removed));
+                        prop_assert_eq!(inventory.quantity(id), before - removed);
+                    }
+                }
+                prop_assert!(inventory.weight.get() <= max_weight);
+                prop_assert!(inventory.volume.get() <= max_volume);
+                prop_assert_eq!(
+                    stacked(&inventory),
+                    (inventory.weight.get(), inventory.volume.get())
+                );
+                prop_assert_eq!(inventory.snapshot().items, stock.items.clone());
+            }
+        }
+    }
+}
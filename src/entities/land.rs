@@ -1,5 +1,7 @@
 use leptos::prelude::*;
 
+use crate::entities::GameError;
+
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Land {
     pub total: RwSignal<u64>,
@@ -10,7 +12,7 @@ impl Land {
     pub fn new(total: u64) -> Self {
         Self {
             total: RwSignal::new(total),
-            available: RwSignal::new(500),
+            available: RwSignal::new(total),
         }
     }
 
@@ -18,27 +20,106 @@ impl Land {
         self.total.get() - self.available.get()
     }
 
-    pub fn use_land(&self, amount: u64) -> Result<(), String> {
+    pub fn use_land(&self, amount: u64) -> Result<(), GameError> {
         if self.available.get() >= amount {
             self.available.update(|l| *l -= amount);
             return Ok(());
         }
-        Err(format!(
-            "Not enough land. Need: {}, available: {}.",
-            amount,
-            self.available.get()
-        ))
+        Err(GameError::InsufficientLand {
+            need: amount,
+            available: self.available.get(),
+        })
     }
 
-    pub fn free_land(&self, amount: u64) -> Result<(), String> {
+    pub fn free_land(&self, amount: u64) -> Result<(), GameError> {
         if self.used() >= amount {
             self.available.update(|l| *l += amount);
             return Ok(());
         }
-        Err(format!(
-            "Trying to free more land than is used. Used: {}, freeing: {}.",
-            self.used(),
-            amount
-        ))
+        Err(GameError::LandNotInUse {
+            freeing: amount,
+            used: self.used(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_owner;
+    use proptest::prelude::*;
+
+    #[test]
+    fn new_land_is_all_available() {
+        let _owner = test_owner();
+        let land = Land::new(120);
+        assert_eq!(land.available.get(), 120);
+        assert_eq!(land.used(), 0);
+    }
+
+    #[test]
+    fn using_more_than_available_changes_nothing() {
+        let _owner = test_owner();
+        let land = Land::new(100);
+        land.use_land(60).unwrap();
+
+        let err = land.use_land(50).unwrap_err();
+        assert_eq!(
+            err,
+            GameError::InsufficientLand {
+                need: 50,
+                available: 40,
+            }
+        );
+        assert_eq!(land.used(), 60);
+    }
+
+    #[test]
+    fn freeing_more_than_used_changes_nothing() {
+        let _owner = test_owner();
+        let land = Land::new(100);
+        land.use_land(30).unwrap();
+
+        let err = land.free_land(31).unwrap_err();
+        assert_eq!(
+            err,
+            GameError::LandNotInUse {
+                freeing: 31,
+                used: 30,
+            }
+        );
+        land.free_land(30).unwrap();
+        assert_eq!(land.available.get(), 100);
+    }
+
+    proptest! {
+        /// Random use/free sequences keep `used + available == total` and match
+        /// a plain counter, failed operations included.
+        #[test]
+        fn use_and_free_conserve_land(
+            total in 0..1_000u64,
+            ops in prop::collection::vec((any::<bool>(), 0..400u64), 0..50),
+        ) {
+            let _owner = test_owner();
+            let land = Land::new(total);
+            let mut used = 0;
+            for (free, amount) in ops {
+                if free {
+                    let result = land.free_land(amount);
+                    prop_assert_eq!(result.is_ok(), amount <= used);
+                    if result.is_ok() {
+                        used -= amount;
+                    }
+                } else {
+                    let result = land.use_land(amount);
+                    prop_assert_eq!(result.is_ok(), amount <= total - used);
+                    if result.is_ok() {
+                        used += amount;
+                    }
+                }
+                prop_assert_eq!(land.used(), used);
+                prop_assert_eq!(land.used() + land.available.get(), total);
+            }
+        }
     }
 }
@@ -0,0 +1,277 @@
+use chrono::{DateTime, Duration, Utc};
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::transaction::Transaction;
+use crate::entities::{GameError, ItemId, Money, Tile};
+
+/// Percentage of the market price kept by the market when buying from players.
+const SPREAD_PERCENT: i64 = 10;
+/// Time for the traded volume behind a price to decay by half.
+const RECOVERY_HALF_LIFE: Duration = Duration::minutes(5);
+/// Prices stay between these multiples of the base price.
+const MIN_PRICE_FACTOR: f64 = 0.2;
+const MAX_PRICE_FACTOR: f64 = 5.;
+
+/// An item the market trades and how strongly its price reacts to volume.
+#[derive(Debug, PartialEq)]
+pub struct MarketItem {
+    pub item_id: ItemId,
+    pub base_price: Money,
+    /// Net units sold that bring the price down to about a third of the base.
+    pub depth: u64,
+}
+
+impl MarketItem {
+    pub fn get(item_id: ItemId) -> Option<&'static Self> {
+        MARKET_ITEMS.iter().find(|item| item.item_id == item_id)
+    }
+
+    pub fn all() -> &'static [Self] {
+        MARKET_ITEMS
+    }
+
+    /// Price of one unit once `pressure` units were sold net.
+    fn unit_price(&self, pressure: f64) -> Money {
+        let factor = (-pressure / self.depth as f64)
+            .exp()
+            .clamp(MIN_PRICE_FACTOR, MAX_PRICE_FACTOR);
+        Money::from_cents((self.base_price.cents() as f64 * factor).round() as i64)
+    }
+}
+
+/// Volume traded per item, the only state behind market prices.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Market {
+    /// Net units sold to the market, negative after net buying. Decays back to
+    /// zero over time so prices recover.
+    pub pressure: Vec<(ItemId, f64)>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Market {
+    pub fn pressure(&self, item_id: ItemId) -> f64 {
+        self.pressure
+            .iter()
+            .find(|(id, _)| *id == item_id)
+            .map(|&(_, pressure)| pressure)
+            .unwrap_or(0.)
+    }
+
+    fn add_pressure(&mut self, item_id: ItemId, amount: f64) {
+        match self.pressure.iter_mut().find(|(id, _)| *id == item_id) {
+            Some((_, pressure)) => *pressure += amount,
+            None => self.pressure.push((item_id, amount)),
+        }
+    }
+
+    /// What the market charges for the next unit of `item_id`.
+    pub fn price(&self, item_id: ItemId) -> Option<Money> {
+        let item = MarketItem::get(item_id)?;
+        Some(item.unit_price(self.pressure(item_id)))
+    }
+
+    /// What the market pays for the next unit of `item_id`.
+    pub fn bid(&self, item_id: ItemId) -> Option<Money> {
+        self.price(item_id)
+            .map(|price| price.percent(100 - SPREAD_PERCENT))
+    }
+
+    /// Total paid for selling `quantity` units, each unit lowering the price
+    /// of the next.
+    pub fn quote_sell(&self, item_id: ItemId, quantity: u64) -> Result<Money, GameError> {
+        let item = MarketItem::get(item_id).ok_or(GameError::NotTraded(item_id))?;
+        let pressure = self.pressure(item_id);
+        Ok((0..quantity)
+            .map(|i| {
+                item.unit_price(pressure + i as f64)
+                    .percent(100 - SPREAD_PERCENT)
+            })
+            .sum())
+    }
+
+    /// Total charged for buying `quantity` units, each unit raising the price
+    /// of the next.
+    pub fn quote_buy(&self, item_id: ItemId, quantity: u64) -> Result<Money, GameError> {
+        let item = MarketItem::get(item_id).ok_or(GameError::NotTraded(item_id))?;
+        let pressure = self.pressure(item_id);
+        Ok((0..quantity)
+            .map(|i| item.unit_price(pressure - i as f64))
+            .sum())
+    }
+
+    /// Sells `quantity` units to the market, returning the proceeds.
+    pub fn sell(&mut self, item_id: ItemId, quantity: u64) -> Result<Money, GameError> {
+        let proceeds = self.quote_sell(item_id, quantity)?;
+        self.add_pressure(item_id, quantity as f64);
+        Ok(proceeds)
+    }
+
+    /// Buys `quantity` units from the market, returning the cost.
+    pub fn buy(&mut self, item_id: ItemId, quantity: u64) -> Result<Money, GameError> {
+        let cost = self.quote_buy(item_id, quantity)?;
+        self.add_pressure(item_id, -(quantity as f64));
+        Ok(cost)
+    }
+
+    /// Decays the traded volume for the time since the last recovery, moving
+    /// every price back towards its base.
+    pub fn recover(&mut self, now: DateTime<Utc>) {
+        if now <= self.updated_at {
+            return;
+        }
+        let elapsed = (now - self.updated_at).num_milliseconds() as f64;
+        let half_life = RECOVERY_HALF_LIFE.num_milliseconds() as f64;
+        let factor = 0.5_f64.powf(elapsed / half_life);
+        for (_, pressure) in self.pressure.iter_mut() {
+            *pressure *= factor;
+        }
+        // Anything below a tenth of a unit no longer moves a price by a cent.
+        self.pressure.retain(|(_, pressure)| pressure.abs() >= 0.1);
+        self.updated_at = now;
+    }
+}
+
+impl Tile<'static> {
+    /// Sells up to `quantity` of `item_id` from the tile inventory. Returns how
+    /// many were sold and the proceeds added to `money`.
+    pub fn sell_items(
+        &self,
+        market: RwSignal<Market>,
+        money: RwSignal<Money>,
+        item_id: ItemId,
+        quantity: u64,
+    ) -> Result<(u64, Money), GameError> {
+        let inventory = self.tile_state.inventory;
+        MarketItem::get(item_id).ok_or(GameError::NotTraded(item_id))?;
+        let stored = inventory.with_untracked(|inv| inv.quantity(item_id));
+        if stored == 0 {
+            return Err(GameError::NotEnoughItems {
+                item_id,
+                requested: quantity,
+                stored,
+            });
+        }
+
+        let sold = inventory
+            .try_update(|inv| inv.remove_item(item_id, quantity))
+            .unwrap_or(Ok(0))?;
+        let proceeds = market
+            .try_update(|market| market.sell(item_id, sold))
+            .unwrap_or(Ok(Money::ZERO))?;
+        money.update(|m| *m += proceeds);
+        Ok((sold, proceeds))
+    }
+
+    /// Buys up to `quantity` of `item_id` into the tile inventory, as many as
+    /// fit. Returns how many were bought and what they cost.
+    pub fn buy_items(
+        &self,
+        market: RwSignal<Market>,
+        money: RwSignal<Money>,
+        item_id: ItemId,
+        quantity: u64,
+    ) -> Result<(u64, Money), GameError> {
+        let inventory = self.tile_state.inventory;
+        let fits = inventory.with_untracked(|inv| inv.fits_max_items(item_id));
+        let bought = quantity.min(fits);
+        if bought == 0 {
+            return Err(GameError::InventoryFull {
+                item_id,
+                requested: quantity,
+                fits,
+            });
+        }
+
+        let cost = market.with_untracked(|market| market.quote_buy(item_id, bought))?;
+        self.tile_state
+            .commit(Transaction::new().pay(money, cost))?;
+        market.try_update(|market| market.buy(item_id, bought));
+        inventory.update(|inv| {
+            inv.add_item(item_id, bought);
+        });
+        Ok((bought, cost))
+    }
+}
+
+const MARKET_ITEMS: &[MarketItem] = &[
+    MarketItem {
+        item_id: ItemId("LOG"),
+        base_price: Money::dollars(20),
+        depth: 500,
+    },
+    MarketItem {
+        item_id: ItemId("H2O"),
+        base_price: Money::dollars(3),
+        depth: 2_000,
+    },
+    MarketItem {
+        item_id: ItemId("BRD"),
+        base_price: Money::dollars(14),
+        depth: 400,
+    },
+    MarketItem {
+        item_id: ItemId("CHR"),
+        base_price: Money::dollars(75),
+        depth: 100,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: ItemId = ItemId("LOG");
+
+    #[test]
+    fn selling_lowers_the_price_and_buying_raises_it() {
+        let mut market = Market::default();
+        let base = market.price(LOG).unwrap();
+        assert_eq!(base, Money::dollars(20));
+
+        market.sell(LOG, 100).unwrap();
+        assert!(market.price(LOG).unwrap() < base);
+        market.buy(LOG, 200).unwrap();
+        assert!(market.price(LOG).unwrap() > base);
+    }
+
+    #[test]
+    fn round_trips_lose_the_spread() {
+        let mut market = Market::default();
+        let cost = market.buy(LOG, 50).unwrap();
+        let proceeds = market.sell(LOG, 50).unwrap();
+        assert!(proceeds < cost);
+        assert!(market.pressure(LOG).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn quotes_match_trades() {
+        let mut market = Market::default();
+        let quote = market.quote_sell(LOG, 30).unwrap();
+        assert_eq!(market.sell(LOG, 30), Ok(quote));
+        let quote = market.quote_buy(LOG, 12).unwrap();
+        assert_eq!(market.buy(LOG, 12), Ok(quote));
+    }
+
+    #[test]
+    fn prices_recover_over_time() {
+        let mut market = Market::default();
+        market.sell(LOG, 400).unwrap();
+        let start = market.updated_at;
+
+        market.recover(start + RECOVERY_HALF_LIFE);
+        assert!((market.pressure(LOG) - 200.).abs() < 1e-6);
+        market.recover(start + RECOVERY_HALF_LIFE * 20);
+        assert_eq!(market.price(LOG), Some(Money::dollars(20)));
+        assert!(market.pressure.is_empty());
+    }
+
+    #[test]
+    fn untraded_items_are_refused() {
+        let mut market = Market::default();
+        assert_eq!(
+            market.sell(ItemId("DBG"), 1),
+            Err(GameError::NotTraded(ItemId("DBG")))
+        );
+    }
+}
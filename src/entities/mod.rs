@@ -1,19 +1,49 @@
 pub mod buildings;
+pub mod error;
 pub mod game_state;
 pub mod housing;
 pub mod inventory;
 pub mod land;
+pub mod market;
+pub mod money;
+pub mod payroll;
 pub mod production;
+pub mod resources;
+pub mod shipment;
+pub mod snapshot;
+pub mod terrain;
 pub mod tile;
+pub mod transaction;
+pub mod vehicles;
 pub mod workers;
 
 pub use buildings::Buildings;
-pub use game_state::GameState;
+pub use error::GameError;
+pub use game_state::{AwaySummary, GameState};
 pub use housing::{Housing, HousingDetails, HousingType};
 pub use inventory::{Inventory, InventoryId, ItemDetails, ItemId, ItemStack};
 pub use land::Land;
+pub use market::{Market, MarketItem};
+pub use money::Money;
+pub use payroll::{Charge, ChargeKind, PAYROLL_PERIOD};
 pub use production::{
-    BuildingId, Production, ProductionDetails, ProductionSlot, ProductionType, Recipe,
+    BuildingId, CatchUp, Production, ProductionBuilding, ProductionDetails, ProductionSlot,
+    ProductionType, Recipe, RecipeId, SlotMode, SlotStatus,
 };
+pub use resources::{Deposit, Forecast, ResourceDetails, ResourceKind, ResourceMap};
+pub use shipment::Shipment;
+pub use snapshot::{GameSnapshot, TileSnapshot};
+pub use terrain::{Terrain, TerrainDetails};
 pub use tile::Tile;
+pub use transaction::Transaction;
+pub use vehicles::{Vehicle, VehicleDetails, VehicleId, VehicleLocation, VehicleType};
 pub use workers::{WorkerDetails, WorkerType, Workers};
+
+/// Sets a fresh reactive owner for the current test, which has to outlive the
+/// signals it creates. On the server every signal needs one to live in.
+#[cfg(test)]
+pub(crate) fn test_owner() -> leptos::prelude::Owner {
+    let owner = leptos::prelude::Owner::new();
+    owner.set();
+    owner
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use std::str::FromStr;
+
+use leptos::prelude::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::entities::GameError;
+
+/// An amount of money in whole cents, so repeated trades never drift.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Self(cents)
+    }
+
+    pub const fn dollars(dollars: i64) -> Self {
+        Self(dollars * 100)
+    }
+
+    pub const fn cents(&self) -> i64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    /// Price of `quantity` units at `self` each.
+    pub fn checked_mul(self, quantity: u64) -> Option<Self> {
+        i64::try_from(quantity)
+            .ok()
+            .and_then(|quantity| self.0.checked_mul(quantity))
+            .map(Self)
+    }
+
+    /// `percent` hundredths of `self`, rounded down to the cent.
+    pub fn percent(self, percent: i64) -> Self {
+        Self(self.0 * percent / 100)
+    }
+
+    /// How many whole units priced at `price` this amount covers.
+    pub fn units_of(self, price: Money) -> u64 {
+        if price.0 <= 0 || self.0 <= 0 {
+            return 0;
+        }
+        (self.0 / price.0) as u64
+    }
+}
+
+impl Add for Money {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0;
+    }
+}
+
+impl Mul<u64> for Money {
+    type Output = Self;
+    fn mul(self, quantity: u64) -> Self {
+        self.checked_mul(quantity)
+            .expect("Money overflow when multiplying by quantity.")
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+/// Formats as `$1,234.56`, or `-$1,234.56` for negative amounts.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        let dollars = (cents / 100).to_string();
+
+        let mut grouped = String::with_capacity(dollars.len() + dollars.len() / 3);
+        for (i, digit) in dollars.chars().enumerate() {
+            if i > 0 && (dollars.len() - i).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        write!(f, "{}${}.{:02}", sign, grouped, cents % 100)
+    }
+}
+
+/// Parses dollars as typed by a player: `12`, `12.5`, `$1,234.56`. More than
+/// two decimals are refused rather than rounded.
+impl FromStr for Money {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (sign, s) = match s.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, s),
+        };
+        let s = s.strip_prefix('$').unwrap_or(s).replace(',', "");
+        let (dollars, cents) = s.split_once('.').unwrap_or((&s, ""));
+
+        let digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+        if !digits(dollars) || (!cents.is_empty() && !digits(cents)) || cents.len() > 2 {
+            return Err(GameError::InvalidAmount);
+        }
+        let dollars: i64 = dollars.parse().map_err(|_| GameError::InvalidAmount)?;
+        let cents: i64 = format!("{:0<2}", cents).parse().unwrap_or(0);
+        dollars
+            .checked_mul(100)
+            .and_then(|total| total.checked_add(cents))
+            .map(|total| Self(sign * total))
+            .ok_or(GameError::InvalidAmount)
+    }
+}
+
+impl IntoRender for Money {
+    type Output = String;
+    fn into_render(self) -> Self::Output {
+        self.to_string()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    /// Reads integer cents. Floats are read as dollars, which is how saves made
+    /// before `Money` stored cash.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl de::Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an amount in cents")
+            }
+
+            fn visit_i64<E: de::Error>(self, cents: i64) -> Result<Money, E> {
+                Ok(Money(cents))
+            }
+
+            fn visit_u64<E: de::Error>(self, cents: u64) -> Result<Money, E> {
+                i64::try_from(cents)
+                    .map(Money)
+                    .map_err(|_| E::custom("amount out of range"))
+            }
+
+            fn visit_f64<E: de::Error>(self, dollars: f64) -> Result<Money, E> {
+                Ok(Money((dollars * 100.).round() as i64))
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_what_display_prints() {
+        for cents in [0, 5, 1_250, 123_456_789, -4_200] {
+            let money = Money::from_cents(cents);
+            assert_eq!(money.to_string().parse(), Ok(money));
+        }
+        assert_eq!("12.5".parse(), Ok(Money::from_cents(1_250)));
+        assert_eq!(" 7 ".parse(), Ok(Money::dollars(7)));
+    }
+
+    #[test]
+    fn refuses_malformed_amounts() {
+        for input in ["", "$", "1.234", "abc", "1.x", ".5", "1e3"] {
+            assert_eq!(input.parse::<Money>(), Err(GameError::InvalidAmount));
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::entities::{HousingType, Money, ProductionType, VehicleType, WorkerType};
+
+/// Time between two payroll runs, wages and upkeep are quoted per period.
+pub const PAYROLL_PERIOD: Duration = Duration::minutes(1);
+/// Number of most recent charges kept in the ledger.
+pub const LEDGER_LEN: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChargeKind {
+    Wages(WorkerType),
+    Housing(HousingType),
+    Building(ProductionType),
+    Vehicle(VehicleType),
+}
+
+impl ChargeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Wages(worker_type) => worker_type.details().name,
+            Self::Housing(housing_type) => housing_type.details().name,
+            Self::Building(production_type) => production_type.details().name,
+            Self::Vehicle(vehicle_type) => vehicle_type.details().name,
+        }
+    }
+
+    /// What one worker, building or vehicle costs every `PAYROLL_PERIOD`.
+    pub fn rate(&self) -> Money {
+        match self {
+            Self::Wages(worker_type) => worker_type.details().wage,
+            Self::Housing(housing_type) => housing_type.details().upkeep,
+            Self::Building(production_type) => production_type.details().upkeep,
+            Self::Vehicle(vehicle_type) => vehicle_type.details().upkeep,
+        }
+    }
+}
+
+/// One line of the payroll ledger.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Charge {
+    pub at: DateTime<Utc>,
+    pub tile_id: String,
+    pub kind: ChargeKind,
+    /// Workers, buildings or vehicles the charge covers.
+    pub count: u64,
+    pub due: Money,
+    pub paid: Money,
+}
+
+impl Charge {
+    pub fn is_paid_in_full(&self) -> bool {
+        self.paid >= self.due
+    }
+}
@@ -1,14 +1,19 @@
 use chrono::{DateTime, Utc};
 use leptos::prelude::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::entities::{ItemId, ItemStack, WorkerType};
+use uuid::Uuid;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+use crate::entities::{GameError, ItemId, Money, ResourceKind, ResourceMap, WorkerType};
+use crate::sim::Storage;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
 pub enum ProductionType {
     Warehouse,
     Sawmill,
     Workshop,
     WaterPump,
+    LoggingCamp,
 }
 
 impl ProductionType {
@@ -18,6 +23,7 @@ impl ProductionType {
             Self::Sawmill,
             Self::Workshop,
             Self::WaterPump,
+            Self::LoggingCamp,
         ]
     }
 
@@ -26,88 +32,742 @@ impl ProductionType {
             Self::Sawmill => ProductionDetails {
                 name: "Sawmill",
                 description: "Processes logs into basic wood products.",
-                cost: 1000.0,
+                cost: Money::dollars(1000),
                 workers: vec![(WorkerType::Basic, 5), (WorkerType::Advanced, 2)],
                 land: 45,
+                upkeep: Money::from_cents(100),
+                extracts: None,
             },
             Self::Warehouse => ProductionDetails {
                 name: "Warehouse",
                 description: "Safe storage of materials.",
-                cost: 600.0,
+                cost: Money::dollars(600),
                 workers: vec![],
                 land: 50,
+                upkeep: Money::from_cents(60),
+                extracts: None,
             },
             Self::Workshop => ProductionDetails {
                 name: "Workshop",
                 description: "Produces wooden items from basic wood products.",
-                cost: 1900.0,
+                cost: Money::dollars(1900),
                 workers: vec![(WorkerType::Advanced, 5), (WorkerType::Expert, 3)],
                 land: 40,
+                upkeep: Money::from_cents(190),
+                extracts: None,
             },
             Self::WaterPump => ProductionDetails {
                 name: "Water Pump",
                 description: "Extracts water from nearby water sources.",
-                cost: 700.0,
+                cost: Money::dollars(700),
                 workers: vec![(WorkerType::Basic, 6)],
                 land: 25,
+                upkeep: Money::from_cents(70),
+                extracts: Some(ResourceKind::Water),
+            },
+            Self::LoggingCamp => ProductionDetails {
+                name: "Logging Camp",
+                description: "Fells trees from the surrounding forest.",
+                cost: Money::dollars(800),
+                workers: vec![(WorkerType::Basic, 8)],
+                land: 40,
+                upkeep: Money::from_cents(80),
+                extracts: Some(ResourceKind::Wood),
             },
         }
     }
 }
 
+/// Condition lost by a building for every batch it delivers.
+const WEAR_PER_BATCH: f64 = 0.002;
+pub const MAX_BUILDING_LEVEL: u32 = 3;
+/// Percentage of the money invested paid back when a building is demolished.
+pub const SALVAGE_PERCENT: i64 = 25;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProductionBuilding {
+    pub id: BuildingId,
+    pub production_type: ProductionType,
+    /// Workers assigned to this building, released when it is idled or demolished.
+    pub workers: Vec<(WorkerType, u64)>,
+    /// Between 0 and 1, worn down by every delivered batch.
+    pub condition: f64,
+    pub level: u32,
+    /// Set while the building is idled, production is frozen and workers released.
+    pub idle_since: Option<DateTime<Utc>>,
+    pub slots: Vec<ProductionSlot>,
+}
+
+impl ProductionBuilding {
+    pub fn new(production_type: ProductionType) -> Self {
+        let slots = if Recipe::for_building(production_type).is_empty() {
+            vec![]
+        } else {
+            vec![ProductionSlot::empty()]
+        };
+        Self {
+            id: BuildingId::new(),
+            production_type,
+            workers: production_type.details().workers,
+            condition: 1.,
+            level: 1,
+            idle_since: None,
+            slots,
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle_since.is_some()
+    }
+
+    pub fn upgrade_cost(&self) -> Money {
+        self.production_type.details().cost * self.level as u64
+    }
+
+    /// Build cost plus every upgrade paid to reach the current level.
+    pub fn invested(&self) -> Money {
+        let level = self.level as u64;
+        self.production_type.details().cost * (1 + level * level.saturating_sub(1) / 2)
+    }
+
+    /// Raises the level, adding another slot and restoring the condition.
+    pub fn upgrade(&mut self) {
+        self.level += 1;
+        self.condition = 1.;
+        if !self.slots.is_empty() {
+            self.slots.push(ProductionSlot::empty());
+        }
+    }
+
+    pub fn set_idle(&mut self, now: DateTime<Utc>) {
+        if self.idle_since.is_none() {
+            self.idle_since = Some(now);
+        }
+    }
+
+    /// Reactivates the building, shifting running batches by the time spent idle.
+    pub fn set_active(&mut self, now: DateTime<Utc>) {
+        let Some(since) = self.idle_since.take() else {
+            return;
+        };
+        let idle_for = now - since;
+        for slot in self
+            .slots
+            .iter_mut()
+            .filter(|slot| slot.mode == SlotMode::Running)
+        {
+            slot.started_at = slot.started_at.map(|t| t + idle_for);
+            slot.next_completion = slot.next_completion.map(|t| t + idle_for);
+        }
+    }
+
+    /// Switches slot `index` to `recipe` if this kind of building can run it.
+    pub fn select_recipe(
+        &mut self,
+        index: usize,
+        recipe: Option<&'static Recipe>,
+        inventory: &mut impl Storage,
+    ) -> Result<(), GameError> {
+        let production_type = self.production_type;
+        match self.slots.get_mut(index) {
+            Some(slot) => slot.select_recipe(
+                recipe.filter(|recipe| recipe.building == production_type),
+                inventory,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Catches up every slot, unless the building is idle.
+    pub fn catch_up(
+        &mut self,
+        now: DateTime<Utc>,
+        inventory: &mut impl Storage,
+        resources: &mut ResourceMap,
+    ) -> CatchUp {
+        let mut total = CatchUp::default();
+        if self.is_idle() {
+            return total;
+        }
+        for slot in self.slots.iter_mut() {
+            total.merge(slot.catch_up(now, self.condition, inventory, resources));
+        }
+        self.condition = (self.condition - WEAR_PER_BATCH * total.batches as f64).max(0.);
+        total
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Production {
-    pub warehouse: RwSignal<u64>,
-    pub sawmill: RwSignal<u64>,
-    pub workshop: RwSignal<u64>,
-    pub water_pump: RwSignal<u64>,
+    pub buildings: RwSignal<Vec<ProductionBuilding>>,
 }
 
 impl Production {
     pub fn new() -> Self {
         Self {
-            warehouse: RwSignal::new(0),
-            sawmill: RwSignal::new(0),
-            workshop: RwSignal::new(0),
-            water_pump: RwSignal::new(0),
+            buildings: RwSignal::new(Vec::new()),
         }
     }
 
-    pub fn build(&self, production_type: ProductionType, amount: u64) {
-        match production_type {
-            ProductionType::WaterPump => self.water_pump.update(|a| *a += amount),
-            ProductionType::Warehouse => self.warehouse.update(|a| *a += amount),
-            ProductionType::Workshop => self.workshop.update(|a| *a += amount),
-            ProductionType::Sawmill => self.sawmill.update(|a| *a += amount),
-        }
+    pub fn count(&self, production_type: ProductionType) -> u64 {
+        self.buildings.with(|buildings| {
+            buildings
+                .iter()
+                .filter(|b| b.production_type == production_type)
+                .count() as u64
+        })
+    }
+
+    /// Adds `amount` new buildings, returning their ids.
+    pub fn build(&self, production_type: ProductionType, amount: u64) -> Vec<BuildingId> {
+        let new: Vec<_> = (0..amount)
+            .map(|_| ProductionBuilding::new(production_type))
+            .collect();
+        let ids = new.iter().map(|b| b.id.clone()).collect();
+        self.buildings.update(|buildings| buildings.extend(new));
+        ids
     }
 
-    // pub fn get_all(&self) -> impl Iterator {}
+    pub fn remove(&self, id: &BuildingId) -> Option<ProductionBuilding> {
+        self.buildings
+            .try_update(|buildings| {
+                let pos = buildings.iter().position(|b| &b.id == id)?;
+                Some(buildings.remove(pos))
+            })
+            .flatten()
+    }
+
+    pub fn contains(&self, id: &BuildingId) -> bool {
+        self.buildings
+            .with_untracked(|buildings| buildings.iter().any(|b| &b.id == id))
+    }
+
+    /// A copy of building `id`, read without tracking.
+    pub fn get_untracked(&self, id: &BuildingId) -> Option<ProductionBuilding> {
+        self.buildings
+            .with_untracked(|buildings| buildings.iter().find(|b| &b.id == id).cloned())
+    }
+
+    pub fn update_building<T>(
+        &self,
+        id: &BuildingId,
+        f: impl FnOnce(&mut ProductionBuilding) -> T,
+    ) -> Option<T> {
+        self.buildings
+            .try_update(|buildings| buildings.iter_mut().find(|b| &b.id == id).map(f))
+            .flatten()
+    }
 }
 
 pub struct ProductionDetails {
     pub name: &'static str,
     pub description: &'static str,
-    pub cost: f64,
+    pub cost: Money,
     pub workers: Vec<(WorkerType, u64)>,
     pub land: u64,
+    /// Maintenance charged per building every `PAYROLL_PERIOD`.
+    pub upkeep: Money,
+    /// Resource of the tile the building draws its output from.
+    pub extracts: Option<ResourceKind>,
 }
 
-#[derive(Clone, Debug, PartialEq, Hash, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub struct BuildingId(pub String);
 
-#[derive(Clone, Debug)]
+impl BuildingId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// First segment of the id, enough to tell buildings apart in the UI.
+    pub fn short(&self) -> &str {
+        self.0.split('-').next().unwrap_or(&self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SlotMode {
+    Stopped,
+    Running,
+    /// Progress of the current batch is frozen since the given time.
+    Paused(DateTime<Utc>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct ProductionSlot {
-    pub building_id: BuildingId,
-    pub recipe: Option<Recipe>,
+    pub recipe: Option<&'static Recipe>,
+    pub mode: SlotMode,
+    /// Batches still to deliver, including the one in progress. `None` repeats forever.
+    pub batches_left: Option<u64>,
     pub started_at: Option<DateTime<Utc>>,
     pub next_completion: Option<DateTime<Utc>>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RecipeId(pub &'static str);
+
+impl Serialize for RecipeId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for RecipeId {
+    /// Resolves the id against `RECIPES` so the `&'static str` is preserved.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Recipe::all()
+            .iter()
+            .map(|recipe| recipe.id)
+            .find(|recipe_id| recipe_id.0 == id)
+            .ok_or_else(|| de::Error::custom(format!("unknown recipe id `{id}`")))
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct Recipe {
+    pub id: RecipeId,
+    pub name: &'static str,
+    pub building: ProductionType,
+    /// Output item, `batch_size` of it is produced per batch.
     pub item_id: ItemId,
     pub batch_size: u64,
     pub batch_duration: chrono::Duration,
-    pub inputs: Option<Vec<ItemStack>>,
+    /// Items consumed when a batch starts.
+    pub inputs: &'static [(ItemId, u64)],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotStatus {
+    Idle,
+    Stopped,
+    Paused,
+    Running,
+    WaitingForInputs,
+    StorageFull,
+    /// Extracting from a deposit with nothing left.
+    Depleted,
+}
+
+impl SlotStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SlotStatus::Idle => "Idle",
+            SlotStatus::Stopped => "Stopped",
+            SlotStatus::Paused => "Paused",
+            SlotStatus::Running => "Running",
+            SlotStatus::WaitingForInputs => "Waiting for inputs",
+            SlotStatus::StorageFull => "Storage full",
+            SlotStatus::Depleted => "Depleted",
+        }
+    }
+}
+
+impl Recipe {
+    pub fn get(id: RecipeId) -> Option<&'static Self> {
+        RECIPES.iter().find(|r| r.id == id)
+    }
+
+    pub fn all() -> &'static [Self] {
+        RECIPES
+    }
+
+    /// Time a batch takes in a building in `condition`, up to twice
+    /// `batch_duration` once it is worn down to nothing.
+    pub fn duration_at(&self, condition: f64) -> chrono::Duration {
+        let factor = 2. - condition.clamp(0., 1.);
+        chrono::Duration::milliseconds(
+            (self.batch_duration.num_milliseconds() as f64 * factor).round() as i64,
+        )
+    }
+
+    /// Recipes that can run in a building of `production_type`.
+    pub fn for_building(production_type: ProductionType) -> Vec<&'static Self> {
+        RECIPES
+            .iter()
+            .filter(|r| r.building == production_type)
+            .collect()
+    }
+
+    /// Recipes whose output is `item_id`.
+    pub fn producing(item_id: ItemId) -> Vec<&'static Self> {
+        RECIPES.iter().filter(|r| r.item_id == item_id).collect()
+    }
+
+    /// Items delivered per batch. Extraction recipes yield `batch_size` where
+    /// their resource is 100% abundant and proportionally less elsewhere, and
+    /// less again as the reserve runs low, see `Deposit::yield_percent`. At
+    /// least one while anything is left.
+    pub fn batch_output(&self, resources: &ResourceMap) -> u64 {
+        match self.building.details().extracts {
+            Some(kind) => {
+                let percent = resources.get(kind).yield_percent() as u64;
+                (self.batch_size * percent).div_ceil(100)
+            }
+            None => self.batch_size,
+        }
+    }
+
+    /// How many of `batches` batches the reserves in `resources` can still
+    /// yield anything for. All of them unless extracting.
+    fn suppliable_batches(&self, batches: u64, resources: &ResourceMap) -> u64 {
+        let Some(kind) = self.building.details().extracts else {
+            return batches;
+        };
+        let mut resources = *resources;
+        let mut supplied = 0;
+        while supplied < batches {
+            let output = self.batch_output(&resources);
+            if output == 0 {
+                break;
+            }
+            resources.get_mut(kind).extract(output);
+            supplied += 1;
+        }
+        supplied
+    }
+
+    /// Delivers `batches` batches one by one, drawing extraction output from
+    /// the reserve so the yield falls as it does. Returns the items produced.
+    fn deliver(&self, batches: u64, resources: &mut ResourceMap) -> u64 {
+        let Some(kind) = self.building.details().extracts else {
+            return self.batch_size * batches;
+        };
+        let mut produced = 0;
+        for _ in 0..batches {
+            let output = self.batch_output(resources);
+            if output == 0 {
+                break;
+            }
+            produced += resources.get_mut(kind).extract(output);
+        }
+        produced
+    }
+
+    /// How many batches the inputs stored in `inventory` are enough for.
+    pub fn startable_batches(&self, inventory: &impl Storage) -> u64 {
+        self.inputs
+            .iter()
+            .filter(|&&(_, per_batch)| per_batch > 0)
+            .map(|&(id, per_batch)| inventory.quantity(id) / per_batch)
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Removes the inputs for `batches` batches from `inventory`, returning what was taken.
+    pub fn take_inputs(&self, inventory: &mut impl Storage, batches: u64) -> Vec<(ItemId, u64)> {
+        self.inputs
+            .iter()
+            .filter_map(|&(id, per_batch)| {
+                let taken = inventory.remove_item(id, per_batch * batches).unwrap_or(0);
+                (taken > 0).then_some((id, taken))
+            })
+            .collect()
+    }
+}
+
+/// Batches credited to a slot in one step, and what they produced and consumed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CatchUp {
+    pub batches: u64,
+    pub produced: Vec<(ItemId, u64)>,
+    pub consumed: Vec<(ItemId, u64)>,
+}
+
+impl CatchUp {
+    /// Adds another result into this one, summing quantities per item.
+    pub fn merge(&mut self, other: CatchUp) {
+        fn add_all(into: &mut Vec<(ItemId, u64)>, from: Vec<(ItemId, u64)>) {
+            for (id, quantity) in from {
+                match into.iter_mut().find(|(existing, _)| *existing == id) {
+                    Some((_, total)) => *total += quantity,
+                    None => into.push((id, quantity)),
+                }
+            }
+        }
+
+        self.batches += other.batches;
+        add_all(&mut self.produced, other.produced);
+        add_all(&mut self.consumed, other.consumed);
+    }
+}
+
+impl ProductionSlot {
+    /// A slot that starts its first batch as soon as the inputs are available.
+    pub fn new(recipe: &'static Recipe) -> Self {
+        Self {
+            recipe: Some(recipe),
+            mode: SlotMode::Running,
+            batches_left: None,
+            started_at: None,
+            next_completion: None,
+        }
+    }
+
+    /// A stopped slot with no recipe selected.
+    pub fn empty() -> Self {
+        Self {
+            recipe: None,
+            mode: SlotMode::Stopped,
+            batches_left: None,
+            started_at: None,
+            next_completion: None,
+        }
+    }
+
+    /// What the slot is doing at `now`, given the inventory and deposits of
+    /// its tile.
+    pub fn status(
+        &self,
+        now: DateTime<Utc>,
+        inventory: &impl Storage,
+        resources: &ResourceMap,
+    ) -> SlotStatus {
+        let Some(recipe) = self.recipe else {
+            return SlotStatus::Idle;
+        };
+        match (self.mode, self.next_completion) {
+            (SlotMode::Stopped, _) => SlotStatus::Stopped,
+            (SlotMode::Paused(_), _) => SlotStatus::Paused,
+            (SlotMode::Running, None) => SlotStatus::WaitingForInputs,
+            (SlotMode::Running, Some(_)) if recipe.batch_output(resources) == 0 => {
+                SlotStatus::Depleted
+            }
+            (SlotMode::Running, Some(completion))
+                if completion <= now
+                    && inventory.fits_max_items(recipe.item_id)
+                        < recipe.batch_output(resources) =>
+            {
+                SlotStatus::StorageFull
+            }
+            (SlotMode::Running, Some(_)) => SlotStatus::Running,
+        }
+    }
+
+    /// Fraction of the current batch completed at `now`, if a batch is in progress.
+    pub fn progress(&self, now: DateTime<Utc>) -> Option<f64> {
+        let (started, completion) = (self.started_at?, self.next_completion?);
+        let now = match self.mode {
+            SlotMode::Paused(at) => at,
+            _ => now,
+        };
+        let total = (completion - started).num_milliseconds().max(1) as f64;
+        let done = (now - started).num_milliseconds() as f64;
+        Some((done / total).clamp(0., 1.))
+    }
+
+    /// Switches to `recipe`, stopping the slot and returning the inputs of the
+    /// batch in progress, see `stop`.
+    pub fn select_recipe(
+        &mut self,
+        recipe: Option<&'static Recipe>,
+        inventory: &mut impl Storage,
+    ) -> Result<(), GameError> {
+        self.stop(inventory)?;
+        self.recipe = recipe;
+        Ok(())
+    }
+
+    /// Starts a stopped slot of a building in `condition`.
+    pub fn start(
+        &mut self,
+        now: DateTime<Utc>,
+        condition: f64,
+        inventory: &mut impl Storage,
+        resources: &mut ResourceMap,
+    ) -> CatchUp {
+        if self.recipe.is_none() || self.mode != SlotMode::Stopped {
+            return CatchUp::default();
+        }
+        if self.batches_left == Some(0) {
+            self.batches_left = None;
+        }
+        self.mode = SlotMode::Running;
+        self.catch_up(now, condition, inventory, resources)
+    }
+
+    pub fn pause(&mut self, now: DateTime<Utc>) {
+        if self.mode == SlotMode::Running {
+            self.mode = SlotMode::Paused(now);
+        }
+    }
+
+    /// Resumes a paused slot, shifting the current batch by the time spent paused.
+    pub fn resume(&mut self, now: DateTime<Utc>) {
+        if let SlotMode::Paused(at) = self.mode {
+            let paused_for = now - at;
+            self.started_at = self.started_at.map(|t| t + paused_for);
+            self.next_completion = self.next_completion.map(|t| t + paused_for);
+            self.mode = SlotMode::Running;
+        }
+    }
+
+    /// Inputs taken for the batch in progress, refunded if it is cancelled.
+    pub fn inputs_in_progress(&self) -> &'static [(ItemId, u64)] {
+        match (self.recipe, self.next_completion) {
+            (Some(recipe), Some(_)) => recipe.inputs,
+            _ => &[],
+        }
+    }
+
+    /// Stops the slot, cancelling the batch in progress and refunding its
+    /// inputs. Refuses, changing nothing, if they do not all fit in `inventory`.
+    pub fn stop(&mut self, inventory: &mut impl Storage) -> Result<(), GameError> {
+        let inputs = self.inputs_in_progress();
+        for (i, &(item_id, requested)) in inputs.iter().enumerate() {
+            let fits = inventory.add_item(item_id, requested);
+            if fits < requested {
+                // Cannot fail, the items are known and were just added.
+                let _ = inventory.remove_item(item_id, fits);
+                for &(id, quantity) in &inputs[..i] {
+                    let _ = inventory.remove_item(id, quantity);
+                }
+                return Err(GameError::InventoryFull {
+                    item_id,
+                    requested,
+                    fits,
+                });
+            }
+        }
+        self.mode = SlotMode::Stopped;
+        self.started_at = None;
+        self.next_completion = None;
+        Ok(())
+    }
+
+    /// Adds `batches` to the queue. A slot repeating forever switches to a fixed count.
+    pub fn queue_batches(&mut self, batches: u64) {
+        let in_progress = self.next_completion.is_some() as u64;
+        self.batches_left = Some(self.batches_left.unwrap_or(in_progress) + batches);
+    }
+
+    pub fn repeat_forever(&mut self) {
+        self.batches_left = None;
+    }
+
+    /// Credits every whole batch finished by `now` and starts the following ones.
+    ///
+    /// Inputs are taken from `inventory` when a batch starts. Delivery is capped by
+    /// the space left in `inventory`; a finished batch that does not fit stays
+    /// pending until room is made. When the inputs run out the slot waits for them
+    /// and resumes from the moment they are available again. Once `batches_left`
+    /// reaches zero the slot stops. Extraction recipes yield according to
+    /// `resources` and draw their output from its reserves, see
+    /// `Recipe::batch_output`. Batches take longer the lower the `condition`
+    /// of the building, see `Recipe::duration_at`.
+    pub fn catch_up(
+        &mut self,
+        now: DateTime<Utc>,
+        condition: f64,
+        inventory: &mut impl Storage,
+        resources: &mut ResourceMap,
+    ) -> CatchUp {
+        let mut result = CatchUp::default();
+        let Some(recipe) = self.recipe else {
+            return result;
+        };
+        let batch_duration = recipe.duration_at(condition);
+        if self.mode != SlotMode::Running || batch_duration <= chrono::Duration::zero() {
+            return result;
+        }
+        let left = self.batches_left.unwrap_or(u64::MAX);
+
+        let Some(completion) = self.next_completion else {
+            if left == 0 {
+                self.mode = SlotMode::Stopped;
+            } else if recipe.startable_batches(inventory) > 0 {
+                result.consumed = recipe.take_inputs(inventory, 1);
+                self.started_at = Some(now);
+                self.next_completion = Some(now + batch_duration);
+            }
+            return result;
+        };
+        if now < completion {
+            return result;
+        }
+
+        let duration_ms = batch_duration.num_milliseconds();
+        let finished = 1 + ((now - completion).num_milliseconds() / duration_ms) as u64;
+        // A depleted deposit delivers nothing and the batch stays pending
+        // until the reserve regrows.
+        let supplied = recipe.suppliable_batches(finished, resources);
+        let fits = inventory.fits_max_items(recipe.item_id) / recipe.batch_output(resources).max(1);
+        // The batch in progress already has its inputs, so one more than is in stock.
+        let startable = recipe.startable_batches(inventory);
+        let delivered = supplied
+            .min(fits)
+            .min(startable.saturating_add(1))
+            .min(left);
+        if delivered == 0 {
+            return result;
+        }
+        let left = left - delivered;
+
+        // Every delivered batch but the first was started inside the window, and the
+        // batch after the last delivered one starts as well if inputs remain.
+        let mut starts = delivered - 1;
+        let last_done = completion + chrono::Duration::milliseconds(duration_ms * starts as i64);
+        if left > 0 && startable > starts {
+            starts += 1;
+            self.started_at = Some(last_done);
+            self.next_completion = Some(last_done + batch_duration);
+        } else {
+            self.started_at = None;
+            self.next_completion = None;
+        }
+        if let Some(batches_left) = self.batches_left.as_mut() {
+            *batches_left = left;
+        }
+        if left == 0 {
+            self.mode = SlotMode::Stopped;
+        }
+
+        result.batches = delivered;
+        result.consumed = recipe.take_inputs(inventory, starts);
+        let produced = recipe.deliver(delivered, resources);
+        if produced > 0 {
+            inventory.add_item(recipe.item_id, produced);
+            result.produced.push((recipe.item_id, produced));
+        }
+        result
+    }
 }
+
+const RECIPES: &[Recipe] = &[
+    Recipe {
+        id: RecipeId("SAW-BRD"),
+        name: "Saw Boards",
+        building: ProductionType::Sawmill,
+        item_id: ItemId("BRD"),
+        batch_size: 2,
+        batch_duration: chrono::Duration::seconds(10),
+        inputs: &[(ItemId("LOG"), 1)],
+    },
+    Recipe {
+        id: RecipeId("WRK-CHR"),
+        name: "Build Chairs",
+        building: ProductionType::Workshop,
+        item_id: ItemId("CHR"),
+        batch_size: 1,
+        batch_duration: chrono::Duration::seconds(20),
+        inputs: &[(ItemId("BRD"), 4)],
+    },
+    Recipe {
+        id: RecipeId("PMP-H2O"),
+        name: "Pump Water",
+        building: ProductionType::WaterPump,
+        item_id: ItemId("H2O"),
+        batch_size: 5,
+        batch_duration: chrono::Duration::seconds(15),
+        inputs: &[],
+    },
+    Recipe {
+        id: RecipeId("CMP-LOG"),
+        name: "Fell Trees",
+        building: ProductionType::LoggingCamp,
+        item_id: ItemId("LOG"),
+        batch_size: 6,
+        batch_duration: chrono::Duration::seconds(20),
+        inputs: &[],
+    },
+];
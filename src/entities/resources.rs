@@ -0,0 +1,236 @@
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{ItemId, PAYROLL_PERIOD};
+
+/// Units of a resource a tile holds per percent of abundance.
+pub const RESERVE_PER_PERCENT: u64 = 50;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ResourceKind {
+    Water,
+    Wood,
+    Stone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceDetails {
+    pub name: &'static str,
+    /// Item extracted from the resource, if any building can extract it yet.
+    pub item_id: Option<ItemId>,
+    /// Fill of the abundance bar.
+    pub color: &'static str,
+    /// Units regrown every `PAYROLL_PERIOD` per 10 points of abundance.
+    pub regrowth: u64,
+}
+
+impl ResourceKind {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Water, Self::Wood, Self::Stone]
+    }
+
+    pub fn details(&self) -> ResourceDetails {
+        match self {
+            ResourceKind::Water => ResourceDetails {
+                name: "Water",
+                item_id: Some(ItemId("H2O")),
+                color: "bg-sky-600",
+                regrowth: 2,
+            },
+            ResourceKind::Wood => ResourceDetails {
+                name: "Wood",
+                item_id: Some(ItemId("LOG")),
+                color: "bg-green-600",
+                regrowth: 1,
+            },
+            ResourceKind::Stone => ResourceDetails {
+                name: "Stone",
+                item_id: None,
+                color: "bg-stone-500",
+                regrowth: 0,
+            },
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// How much of one resource a tile has.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    /// From 0 to 100, scales the output of the buildings extracting it.
+    pub abundance: u32,
+    /// Units left to extract.
+    pub reserve: u64,
+    /// Reserve of the untouched deposit, regrowth stops there.
+    pub capacity: u64,
+}
+
+/// Where a deposit is heading at the current rate of extraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Forecast {
+    /// Nothing extracts it.
+    Untouched,
+    /// Regrowth keeps up with extraction.
+    Sustained,
+    /// Runs dry after this long, or already has.
+    DepletedIn(Duration),
+}
+
+impl Deposit {
+    pub fn new(abundance: u32) -> Self {
+        let capacity = abundance as u64 * RESERVE_PER_PERCENT;
+        Self {
+            abundance,
+            reserve: capacity,
+            capacity,
+        }
+    }
+
+    /// Abundance extraction yields by. Full while at least half the reserve
+    /// is left, then falling with the reserve, never to zero before it is
+    /// all gone.
+    pub fn yield_percent(&self) -> u32 {
+        if self.capacity == 0 {
+            return 0;
+        }
+        let left = (self.reserve * 2).min(self.capacity);
+        (self.abundance as u64 * left).div_ceil(self.capacity) as u32
+    }
+
+    /// Takes up to `amount` from the reserve, returning how much was taken.
+    pub fn extract(&mut self, amount: u64) -> u64 {
+        let taken = amount.min(self.reserve);
+        self.reserve -= taken;
+        taken
+    }
+
+    /// One `PAYROLL_PERIOD` of regrowth.
+    pub fn regrow(&mut self, kind: ResourceKind) {
+        let regrown = kind.details().regrowth * self.abundance as u64 / 10;
+        self.reserve = (self.reserve + regrown).min(self.capacity);
+    }
+
+    /// Forecast for `extracted` units taken every `PAYROLL_PERIOD`.
+    pub fn forecast(&self, kind: ResourceKind, extracted: u64) -> Forecast {
+        if extracted == 0 {
+            return Forecast::Untouched;
+        }
+        let regrown = kind.details().regrowth * self.abundance as u64 / 10;
+        if regrown >= extracted {
+            return Forecast::Sustained;
+        }
+        let periods = self.reserve.div_ceil(extracted - regrown);
+        Forecast::DepletedIn(PAYROLL_PERIOD * periods as i32)
+    }
+}
+
+/// Deposit of every resource kind on a tile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceMap([Deposit; 3]);
+
+impl ResourceMap {
+    /// Deposits with the given abundances, the others are empty.
+    pub fn new(abundances: &[(ResourceKind, u32)]) -> Self {
+        let mut map = Self::default();
+        for &(kind, abundance) in abundances {
+            map.set(kind, Deposit::new(abundance));
+        }
+        map
+    }
+
+    pub fn get(&self, kind: ResourceKind) -> Deposit {
+        self.0[kind.index()]
+    }
+
+    pub fn set(&mut self, kind: ResourceKind, deposit: Deposit) {
+        self.0[kind.index()] = deposit;
+    }
+
+    pub fn abundance(&self, kind: ResourceKind) -> u32 {
+        self.get(kind).abundance
+    }
+
+    pub fn get_mut(&mut self, kind: ResourceKind) -> &mut Deposit {
+        &mut self.0[kind.index()]
+    }
+
+    /// One `PAYROLL_PERIOD` of regrowth for every deposit.
+    pub fn regrow(&mut self) {
+        for kind in ResourceKind::all() {
+            self.get_mut(kind).regrow(kind);
+        }
+    }
+
+    /// Resources present on the tile, most abundant first.
+    pub fn present(&self) -> Vec<(ResourceKind, Deposit)> {
+        let mut present: Vec<_> = ResourceKind::all()
+            .into_iter()
+            .map(|kind| (kind, self.get(kind)))
+            .filter(|(_, deposit)| deposit.abundance > 0)
+            .collect();
+        present.sort_by_key(|(_, deposit)| std::cmp::Reverse(deposit.abundance));
+        present
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn present_resources_come_most_abundant_first() {
+        let map = ResourceMap::new(&[(ResourceKind::Water, 20), (ResourceKind::Wood, 75)]);
+
+        assert_eq!(map.abundance(ResourceKind::Stone), 0);
+        assert_eq!(map.get(ResourceKind::Water).reserve, 1_000);
+        assert_eq!(
+            map.present()
+                .iter()
+                .map(|&(kind, _)| kind)
+                .collect::<Vec<_>>(),
+            vec![ResourceKind::Wood, ResourceKind::Water]
+        );
+    }
+
+    #[test]
+    fn yields_fall_once_half_the_reserve_is_gone() {
+        let mut deposit = Deposit::new(80);
+        assert_eq!(deposit.extract(2_000), 2_000);
+        assert_eq!(deposit.yield_percent(), 80);
+        assert_eq!(deposit.extract(1_000), 1_000);
+        assert_eq!(deposit.yield_percent(), 40);
+        assert_eq!(deposit.extract(5_000), 1_000);
+        assert_eq!(deposit.yield_percent(), 0);
+    }
+
+    #[test]
+    fn only_renewable_resources_regrow() {
+        let mut map = ResourceMap::new(&[(ResourceKind::Wood, 80), (ResourceKind::Stone, 90)]);
+        map.get_mut(ResourceKind::Wood).extract(100);
+        map.get_mut(ResourceKind::Stone).extract(100);
+        map.regrow();
+
+        assert_eq!(map.get(ResourceKind::Wood).reserve, 4_000 - 100 + 8);
+        assert_eq!(map.get(ResourceKind::Stone).reserve, 4_500 - 100);
+        for _ in 0..20 {
+            map.regrow();
+        }
+        assert_eq!(map.get(ResourceKind::Wood).reserve, 4_000);
+    }
+
+    #[test]
+    fn forecast_nets_out_regrowth() {
+        let deposit = Deposit::new(60);
+        let water = ResourceKind::Water;
+
+        assert_eq!(deposit.forecast(water, 0), Forecast::Untouched);
+        assert_eq!(deposit.forecast(water, 12), Forecast::Sustained);
+        assert_eq!(
+            deposit.forecast(water, 22),
+            Forecast::DepletedIn(PAYROLL_PERIOD * 300)
+        );
+    }
+}
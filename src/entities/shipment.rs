@@ -0,0 +1,221 @@
+use chrono::{DateTime, Utc};
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{GameError, GameState, Inventory, ItemId, Tile, VehicleId, VehicleLocation};
+use crate::sim::Storage;
+
+/// Trip of a vehicle between two tiles. The cargo travels in the vehicle
+/// inventory and is unloaded once it arrived, as far as the destination has
+/// room; the rest stays on board.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Shipment {
+    pub from: String,
+    pub to: String,
+    pub departed_at: DateTime<Utc>,
+    pub arrives_at: DateTime<Utc>,
+}
+
+impl Shipment {
+    pub fn has_arrived(&self, now: DateTime<Utc>) -> bool {
+        now >= self.arrives_at
+    }
+
+    /// Share of the trip done, from 0 to 1.
+    pub fn progress(&self, now: DateTime<Utc>) -> f64 {
+        let total = (self.arrives_at - self.departed_at).num_milliseconds();
+        if total <= 0 {
+            return 1.;
+        }
+        let done = (now - self.departed_at).num_milliseconds();
+        (done as f64 / total as f64).clamp(0., 1.)
+    }
+}
+
+impl GameState {
+    /// Loads `cargo` from the tile the vehicle is parked at and sends it to
+    /// `to` along the cheapest route, see `GameState::route`. Everything is
+    /// checked before any item moves; a vehicle may also leave empty or with
+    /// only what it already carries.
+    pub fn dispatch(
+        &self,
+        vehicle_id: &VehicleId,
+        to: &Tile<'static>,
+        cargo: &[(ItemId, u64)],
+        now: DateTime<Utc>,
+    ) -> Result<Shipment, GameError> {
+        let vehicle = self.vehicle(vehicle_id).ok_or(GameError::VehicleNotFound)?;
+        let from = vehicle
+            .location
+            .with_untracked(|location| location.parked_at().map(str::to_string))
+            .ok_or(GameError::VehicleEnRoute)?;
+        let Some(from) = self.tiles.iter().find(|tile| tile.id == from) else {
+            return Err(GameError::TileNotOwned);
+        };
+        if from.id == to.id {
+            return Err(GameError::SameTile);
+        }
+        for tile in [from, to] {
+            if !tile.is_owned.get_untracked() {
+                return Err(GameError::TileNotOwned);
+            }
+        }
+        let route = self.route(from, to).ok_or(GameError::NoRoute)?;
+
+        let inventory = from.tile_state.inventory;
+        let mut hold = vehicle.inventory.with_untracked(Inventory::snapshot);
+        let mut loading = hold.clone();
+        loading.items.clear();
+        for &(item_id, requested) in cargo {
+            let stored = inventory.with_untracked(|inv| inv.quantity(item_id));
+            let already_loaded = loading.quantity(item_id);
+            if stored < already_loaded + requested {
+                return Err(GameError::NotEnoughItems {
+                    item_id,
+                    requested: already_loaded + requested,
+                    stored,
+                });
+            }
+            let fits = hold.fits_max_items(item_id);
+            if fits < requested {
+                return Err(GameError::InventoryFull {
+                    item_id,
+                    requested,
+                    fits,
+                });
+            }
+            hold.add_item(item_id, requested);
+            loading.items.push((item_id, requested));
+        }
+
+        for (item_id, quantity) in loading.items {
+            // Checked above, the items are stored and fit in the vehicle.
+            inventory.update(|inv| {
+                let _ = inv.remove_item(item_id, quantity);
+            });
+            vehicle.inventory.update(|inv| {
+                inv.add_item(item_id, quantity);
+            });
+        }
+        let shipment = Shipment {
+            from: from.id.to_string(),
+            to: to.id.to_string(),
+            departed_at: now,
+            arrives_at: now + vehicle.vehicle_type.travel_time(route.cost),
+        };
+        vehicle
+            .location
+            .set(VehicleLocation::EnRoute(shipment.clone()));
+        Ok(shipment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::entities::{test_owner, Money, Vehicle, VehicleType};
+
+    const LOG: ItemId = ItemId("LOG");
+
+    /// Owned tiles A and B, a tile C nobody owns and a cart parked at A, on a
+    /// four by four map of plains. D is owned but off the map.
+    fn game_with_tiles() -> (GameState, Vehicle) {
+        let mut game_state = GameState::new();
+        let named = [("A", 0, 0), ("B", 2, 3), ("C", 0, 1), ("D", 9, 9)];
+        let unnamed = (0..4)
+            .flat_map(|row| (0..4).map(move |col| ("", row, col)))
+            .filter(|&(_, row, col)| !named.iter().any(|&(_, r, c)| (r, c) == (row, col)));
+        for (id, row, col) in named.into_iter().chain(unnamed) {
+            let tile = Tile {
+                id,
+                price: Money::ZERO,
+                row,
+                col,
+                ..Tile::new()
+            };
+            tile.is_owned.set(matches!(id, "A" | "B" | "D"));
+            game_state.tiles.push(tile);
+        }
+        game_state.tiles[0].tile_state.inventory.update(|inv| {
+            inv.add_item(LOG, 100);
+        });
+        let cart = game_state
+            .buy_vehicle(&game_state.tiles[0], VehicleType::Cart)
+            .unwrap();
+        (game_state, cart)
+    }
+
+    #[test]
+    fn dispatch_loads_the_cargo_and_sets_the_arrival() {
+        let _owner = test_owner();
+        let (game_state, cart) = game_with_tiles();
+        let (a, b) = (game_state.tiles[0], game_state.tiles[1]);
+        let now = DateTime::UNIX_EPOCH;
+        let shipment = game_state
+            .dispatch(&cart.id, &b, &[(LOG, 10), (LOG, 5)], now)
+            .unwrap();
+
+        assert_eq!(cart.inventory.with(|inv| inv.quantity(LOG)), 15);
+        assert_eq!(a.tile_state.inventory.with(|inv| inv.quantity(LOG)), 85);
+        // Four hexes of plains, two minutes each for a cart.
+        assert_eq!(shipment.arrives_at, now + Duration::minutes(8));
+        assert_eq!(cart.location.get(), VehicleLocation::EnRoute(shipment));
+        assert_eq!(
+            game_state.dispatch(&cart.id, &a, &[], now),
+            Err(GameError::VehicleEnRoute)
+        );
+    }
+
+    #[test]
+    fn dispatch_refuses_without_changing_anything() {
+        let _owner = test_owner();
+        let (game_state, cart) = game_with_tiles();
+        let (a, b, c) = (
+            game_state.tiles[0],
+            game_state.tiles[1],
+            game_state.tiles[2],
+        );
+        let now = DateTime::UNIX_EPOCH;
+
+        assert_eq!(
+            game_state.dispatch(&cart.id, &b, &[(LOG, 20), (LOG, 6)], now),
+            Err(GameError::InventoryFull {
+                item_id: LOG,
+                requested: 6,
+                fits: 5
+            })
+        );
+        assert_eq!(
+            game_state.dispatch(&cart.id, &b, &[(LOG, 101)], now),
+            Err(GameError::NotEnoughItems {
+                item_id: LOG,
+                requested: 101,
+                stored: 100
+            })
+        );
+        assert_eq!(
+            game_state.dispatch(&cart.id, &c, &[(LOG, 1)], now),
+            Err(GameError::TileNotOwned)
+        );
+        assert_eq!(
+            game_state.dispatch(&cart.id, &a, &[(LOG, 1)], now),
+            Err(GameError::SameTile)
+        );
+        assert_eq!(
+            game_state.dispatch(&cart.id, &game_state.tiles[3], &[(LOG, 1)], now),
+            Err(GameError::NoRoute)
+        );
+        assert_eq!(
+            game_state.dispatch(&VehicleId::new(), &b, &[], now),
+            Err(GameError::VehicleNotFound)
+        );
+        assert_eq!(a.tile_state.inventory.with(|inv| inv.quantity(LOG)), 100);
+        assert_eq!(cart.inventory.with(|inv| inv.quantity(LOG)), 0);
+        assert_eq!(
+            cart.location.get(),
+            VehicleLocation::Parked("A".to_string())
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use chrono::{DateTime, Utc};
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::tile::TileState;
+use crate::entities::workers::WorkerCategory;
+use crate::entities::{
+    BuildingId, GameState, Housing, HousingType, Inventory, ItemDetails, ItemStack, Land, Market,
+    Money, Production, ProductionBuilding, ProductionSlot, ProductionType, Recipe, RecipeId,
+    ResourceMap, SlotMode, Tile, Vehicle, VehicleId, VehicleLocation, VehicleType, WorkerType,
+    Workers,
+};
+use crate::sim::Stock;
+
+/// Plain, serializable copy of the whole `GameState`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub cash: Money,
+    pub saved_at: DateTime<Utc>,
+    /// Missing in saves made before payroll existed.
+    #[serde(default)]
+    pub last_payroll: Option<DateTime<Utc>>,
+    pub tiles: Vec<TileSnapshot>,
+    /// Missing in saves made before the market existed.
+    #[serde(default)]
+    pub market: Market,
+    /// Missing in saves made before vehicles existed.
+    #[serde(default)]
+    pub vehicles: Vec<VehicleSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TileSnapshot {
+    pub id: String,
+    pub is_owned: bool,
+    pub inventory: Stock,
+    pub housing: HousingSnapshot,
+    pub production: Vec<ProductionBuildingSnapshot>,
+    pub land: LandSnapshot,
+    pub workers: WorkersSnapshot,
+    /// Reserves left on the tile. Missing in saves made before tiles had
+    /// resources, the generated deposits are kept then.
+    #[serde(default)]
+    pub resources: Option<ResourceMap>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VehicleSnapshot {
+    pub id: VehicleId,
+    pub vehicle_type: VehicleType,
+    pub cargo: Stock,
+    pub location: VehicleLocation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HousingSnapshot {
+    pub cheap: u64,
+    pub standard: u64,
+    pub fancy: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProductionBuildingSnapshot {
+    pub id: String,
+    pub production_type: ProductionType,
+    pub workers: Vec<(WorkerType, u64)>,
+    pub condition: f64,
+    pub level: u32,
+    pub idle_since: Option<DateTime<Utc>>,
+    pub slots: Vec<ProductionSlotSnapshot>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LandSnapshot {
+    pub total: u64,
+    pub available: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkersSnapshot {
+    pub basic: WorkerCategorySnapshot,
+    pub advanced: WorkerCategorySnapshot,
+    pub expert: WorkerCategorySnapshot,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkerCategorySnapshot {
+    pub total: u64,
+    pub assigned: u64,
+}
+
+impl HousingSnapshot {
+    pub fn count(&self, housing_type: HousingType) -> u64 {
+        match housing_type {
+            HousingType::Cheap => self.cheap,
+            HousingType::Standard => self.standard,
+            HousingType::Fancy => self.fancy,
+        }
+    }
+}
+
+impl WorkersSnapshot {
+    pub fn category(&self, worker_type: WorkerType) -> &WorkerCategorySnapshot {
+        match worker_type {
+            WorkerType::Basic => &self.basic,
+            WorkerType::Advanced => &self.advanced,
+            WorkerType::Expert => &self.expert,
+        }
+    }
+
+    pub fn category_mut(&mut self, worker_type: WorkerType) -> &mut WorkerCategorySnapshot {
+        match worker_type {
+            WorkerType::Basic => &mut self.basic,
+            WorkerType::Advanced => &mut self.advanced,
+            WorkerType::Expert => &mut self.expert,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProductionSlotSnapshot {
+    pub recipe: Option<RecipeId>,
+    pub mode: SlotMode,
+    pub batches_left: Option<u64>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub next_completion: Option<DateTime<Utc>>,
+}
+
+impl GameState {
+    pub fn snapshot(&self) -> GameSnapshot {
+        self.snapshot_at(Utc::now())
+    }
+
+    pub fn snapshot_at(&self, saved_at: DateTime<Utc>) -> GameSnapshot {
+        GameSnapshot {
+            cash: self.cash.get_untracked(),
+            saved_at,
+            last_payroll: Some(self.last_payroll.get_untracked()),
+            tiles: self.tiles.iter().map(Tile::snapshot).collect(),
+            market: self.market.get_untracked(),
+            vehicles: self
+                .vehicles
+                .with_untracked(|vehicles| vehicles.iter().map(Vehicle::snapshot).collect()),
+        }
+    }
+
+    /// Writes a snapshot back into the existing signals. Tiles and vehicles are
+    /// matched by id, tiles missing from the snapshot are left untouched while
+    /// vehicles follow the snapshot. Signals whose value did not change are not
+    /// notified.
+    pub fn restore(&self, snapshot: &GameSnapshot) {
+        set_if_changed(self.cash, snapshot.cash);
+        set_if_changed(
+            self.last_payroll,
+            snapshot.last_payroll.unwrap_or(snapshot.saved_at),
+        );
+        set_if_changed(self.clock, snapshot.saved_at);
+        set_if_changed(self.market, snapshot.market.clone());
+        let current = self.vehicles.get_untracked();
+        let vehicles: Vec<_> = snapshot
+            .vehicles
+            .iter()
+            .map(|saved| {
+                let vehicle = current
+                    .iter()
+                    .find(|vehicle| vehicle.id == saved.id)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        Vehicle::new(saved.id.clone(), saved.vehicle_type, saved.location.clone())
+                    });
+                vehicle.restore(saved);
+                vehicle
+            })
+            .collect();
+        set_if_changed(self.vehicles, vehicles);
+        for tile in &self.tiles {
+            if let Some(saved) = snapshot.tiles.iter().find(|saved| saved.id == tile.id) {
+                tile.restore(saved);
+            }
+        }
+    }
+}
+
+impl Tile<'static> {
+    pub fn snapshot(&self) -> TileSnapshot {
+        let state = self.tile_state;
+        TileSnapshot {
+            id: self.id.to_string(),
+            is_owned: self.is_owned.get_untracked(),
+            inventory: state.inventory.with_untracked(Inventory::snapshot),
+            housing: state.buildings.housing.snapshot(),
+            production: state.buildings.production.snapshot(),
+            land: state.land.snapshot(),
+            workers: state.workers.snapshot(),
+            resources: Some(state.resources.get_untracked()),
+        }
+    }
+
+    pub fn restore(&self, snapshot: &TileSnapshot) {
+        let TileState {
+            inventory,
+            buildings,
+            land,
+            workers,
+            resources,
+        } = self.tile_state;
+
+        set_if_changed(self.is_owned, snapshot.is_owned);
+        inventory.with_untracked(|inv| inv.restore(&snapshot.inventory));
+        buildings.housing.restore(&snapshot.housing);
+        buildings.production.restore(&snapshot.production);
+        land.restore(&snapshot.land);
+        workers.restore(&snapshot.workers);
+        if let Some(reserves) = snapshot.resources {
+            set_if_changed(resources, reserves);
+        }
+    }
+}
+
+impl Vehicle {
+    pub fn snapshot(&self) -> VehicleSnapshot {
+        VehicleSnapshot {
+            id: self.id.clone(),
+            vehicle_type: self.vehicle_type,
+            cargo: self.inventory.with_untracked(Inventory::snapshot),
+            location: self.location.get_untracked(),
+        }
+    }
+
+    pub fn restore(&self, snapshot: &VehicleSnapshot) {
+        self.inventory
+            .with_untracked(|inv| inv.restore(&snapshot.cargo));
+        set_if_changed(self.location, snapshot.location.clone());
+    }
+}
+
+impl Inventory {
+    pub fn snapshot(&self) -> Stock {
+        Stock {
+            items: self.items.with_untracked(|items| {
+                items
+                    .iter()
+                    .map(|stack| (stack.id, stack.quantity.get_untracked()))
+                    .collect()
+            }),
+            max_volume: self.max_volume.get_untracked(),
+            max_weight: self.max_weight.get_untracked(),
+        }
+    }
+
+    /// Replaces the stored items, recomputing weight and volume from the stacks.
+    /// Stacks already present keep their signal so views bound to them survive.
+    pub fn restore(&self, snapshot: &Stock) {
+        let (mut weight, mut volume) = (0, 0);
+        let current = self.items.get_untracked();
+        let mut items = Vec::with_capacity(snapshot.items.len());
+        for &(id, quantity) in &snapshot.items {
+            let Some(details) = ItemDetails::get(id) else {
+                continue;
+            };
+            weight += details.weight * quantity;
+            volume += details.volume * quantity;
+            match current.iter().find(|stack| stack.id == id) {
+                Some(stack) => {
+                    set_if_changed(stack.quantity, quantity);
+                    items.push(*stack);
+                }
+                None => items.push(ItemStack {
+                    id,
+                    quantity: RwSignal::new(quantity),
+                }),
+            }
+        }
+
+        if items != current {
+            self.items.set(items);
+        }
+        set_if_changed(self.max_volume, snapshot.max_volume);
+        set_if_changed(self.max_weight, snapshot.max_weight);
+        set_if_changed(self.weight, weight);
+        set_if_changed(self.volume, volume);
+    }
+}
+
+impl Housing {
+    pub fn snapshot(&self) -> HousingSnapshot {
+        HousingSnapshot {
+            cheap: self.cheap.get_untracked(),
+            standard: self.standard.get_untracked(),
+            fancy: self.fancy.get_untracked(),
+        }
+    }
+
+    pub fn restore(&self, snapshot: &HousingSnapshot) {
+        set_if_changed(self.cheap, snapshot.cheap);
+        set_if_changed(self.standard, snapshot.standard);
+        set_if_changed(self.fancy, snapshot.fancy);
+    }
+}
+
+impl Production {
+    pub fn snapshot(&self) -> Vec<ProductionBuildingSnapshot> {
+        self.buildings.with_untracked(|buildings| {
+            buildings.iter().map(ProductionBuilding::snapshot).collect()
+        })
+    }
+
+    pub fn restore(&self, snapshot: &[ProductionBuildingSnapshot]) {
+        set_if_changed(
+            self.buildings,
+            snapshot
+                .iter()
+                .map(ProductionBuilding::from_snapshot)
+                .collect(),
+        );
+    }
+}
+
+impl ProductionBuilding {
+    pub fn snapshot(&self) -> ProductionBuildingSnapshot {
+        ProductionBuildingSnapshot {
+            id: self.id.0.clone(),
+            production_type: self.production_type,
+            workers: self.workers.clone(),
+            condition: self.condition,
+            level: self.level,
+            idle_since: self.idle_since,
+            slots: self.slots.iter().map(ProductionSlot::snapshot).collect(),
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &ProductionBuildingSnapshot) -> Self {
+        Self {
+            id: BuildingId(snapshot.id.clone()),
+            production_type: snapshot.production_type,
+            workers: snapshot.workers.clone(),
+            condition: snapshot.condition,
+            level: snapshot.level,
+            idle_since: snapshot.idle_since,
+            slots: snapshot
+                .slots
+                .iter()
+                .map(ProductionSlot::from_snapshot)
+                .collect(),
+        }
+    }
+}
+
+impl Land {
+    pub fn snapshot(&self) -> LandSnapshot {
+        LandSnapshot {
+            total: self.total.get_untracked(),
+            available: self.available.get_untracked(),
+        }
+    }
+
+    pub fn restore(&self, snapshot: &LandSnapshot) {
+        set_if_changed(self.total, snapshot.total);
+        set_if_changed(self.available, snapshot.available);
+    }
+}
+
+impl Workers {
+    pub fn snapshot(&self) -> WorkersSnapshot {
+        WorkersSnapshot {
+            basic: self.basic.snapshot(),
+            advanced: self.advanced.snapshot(),
+            expert: self.expert.snapshot(),
+        }
+    }
+
+    pub fn restore(&self, snapshot: &WorkersSnapshot) {
+        self.basic.restore(&snapshot.basic);
+        self.advanced.restore(&snapshot.advanced);
+        self.expert.restore(&snapshot.expert);
+    }
+}
+
+impl WorkerCategory {
+    pub fn snapshot(&self) -> WorkerCategorySnapshot {
+        untrack(|| WorkerCategorySnapshot {
+            total: self.total(),
+            assigned: self.assigned(),
+        })
+    }
+
+    pub fn restore(&self, snapshot: &WorkerCategorySnapshot) {
+        self.set(snapshot.total, snapshot.assigned);
+    }
+}
+
+impl ProductionSlot {
+    pub fn snapshot(&self) -> ProductionSlotSnapshot {
+        ProductionSlotSnapshot {
+            recipe: self.recipe.map(|recipe| recipe.id),
+            mode: self.mode,
+            batches_left: self.batches_left,
+            started_at: self.started_at,
+            next_completion: self.next_completion,
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &ProductionSlotSnapshot) -> Self {
+        Self {
+            recipe: snapshot.recipe.and_then(Recipe::get),
+            mode: snapshot.mode,
+            batches_left: snapshot.batches_left,
+            started_at: snapshot.started_at,
+            next_completion: snapshot.next_completion,
+        }
+    }
+}
+
+fn set_if_changed<T: PartialEq + Send + Sync + 'static>(signal: RwSignal<T>, value: T) {
+    if signal.with_untracked(|current| *current != value) {
+        signal.set(value);
+    }
+}
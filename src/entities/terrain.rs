@@ -0,0 +1,92 @@
+use crate::entities::Money;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum Terrain {
+    #[default]
+    Plains,
+    Forest,
+    River,
+    Hills,
+    Mountain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainDetails {
+    pub name: &'static str,
+    /// Opening words of the tile description, one is picked per tile.
+    pub descriptions: &'static [&'static str],
+    /// Price of a tile before its resources are accounted for.
+    pub base_price: Money,
+    /// Fill of the hex on the tile map.
+    pub fill: &'static str,
+    /// Cost of entering the hex when routing across the map.
+    pub move_cost: u32,
+}
+
+impl Terrain {
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Plains,
+            Self::Forest,
+            Self::River,
+            Self::Hills,
+            Self::Mountain,
+        ]
+    }
+
+    pub fn details(&self) -> TerrainDetails {
+        match self {
+            Terrain::Plains => TerrainDetails {
+                name: "Plains",
+                descriptions: &["Open grassland", "Flat farmland", "Windswept meadows"],
+                base_price: Money::dollars(400),
+                fill: "fill-lime-900",
+                move_cost: 1,
+            },
+            Terrain::Forest => TerrainDetails {
+                name: "Forest",
+                descriptions: &[
+                    "Dense woodland",
+                    "Old pine forest",
+                    "Mixed forest with a few clearings",
+                ],
+                base_price: Money::dollars(450),
+                fill: "fill-green-950",
+                move_cost: 2,
+            },
+            Terrain::River => TerrainDetails {
+                name: "River",
+                descriptions: &[
+                    "A slow river through marshland",
+                    "Riverbanks and reed beds",
+                    "A wide valley floor cut by streams",
+                ],
+                base_price: Money::dollars(550),
+                fill: "fill-sky-900",
+                move_cost: 3,
+            },
+            Terrain::Hills => TerrainDetails {
+                name: "Hills",
+                descriptions: &[
+                    "Rolling hills",
+                    "Rocky slopes with thin soil",
+                    "Terraced hillsides",
+                ],
+                base_price: Money::dollars(300),
+                fill: "fill-amber-900",
+                move_cost: 2,
+            },
+            Terrain::Mountain => TerrainDetails {
+                name: "Mountain",
+                descriptions: &[
+                    "Steep mountainside",
+                    "Bare peaks and scree",
+                    "A high mountain pass",
+                ],
+                base_price: Money::dollars(200),
+                fill: "fill-stone-700",
+                move_cost: 4,
+            },
+        }
+    }
+}
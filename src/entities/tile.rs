@@ -1,18 +1,25 @@
 use leptos::prelude::*;
 
-use crate::entities::{BuildingId, Inventory, ItemId, Recipe};
+use chrono::Utc;
+
+use crate::entities::production::SALVAGE_PERCENT;
+use crate::entities::transaction::Transaction;
+use crate::entities::{BuildingId, GameError, Inventory, ItemId, Money, Recipe, RecipeId, Terrain};
 use crate::entities::{
-    Buildings, HousingType, Land, ProductionSlot, ProductionType, WorkerType, Workers,
+    Buildings, Forecast, HousingType, Land, ProductionBuilding, ProductionSlot, ProductionType,
+    ResourceKind, ResourceMap, WorkerType, Workers,
 };
+use crate::sim;
+use crate::world::hex::Hex;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Tile<'a> {
     pub id: &'a str,
     pub description: &'a str,
-    pub resources: &'a [&'a str],
-    pub price: f64,
+    pub price: Money,
     pub row: u32,
     pub col: u32,
+    pub terrain: Terrain,
     pub is_owned: RwSignal<bool>,
     pub tile_state: TileState,
 }
@@ -23,7 +30,8 @@ pub struct TileState {
     pub buildings: Buildings,
     pub land: Land,
     pub workers: Workers,
-    pub production_queue: RwSignal<Vec<ProductionSlot>>,
+    /// Deposits as generated, drawn down by extraction.
+    pub resources: RwSignal<ResourceMap>,
 }
 
 impl TileState {
@@ -33,29 +41,83 @@ impl TileState {
             buildings: Buildings::new(),
             land: Land::new(500),
             workers: Workers::new(),
-            production_queue: RwSignal::new(Vec::new()),
+            resources: RwSignal::new(ResourceMap::default()),
         }
     }
+
+    /// A staffed tile with a Sawmill feeding a Workshop.
     pub fn with_production() -> Self {
-        let now = chrono::Utc::now();
-        let slot = ProductionSlot {
-            building_id: BuildingId("avc".to_string()),
-            next_completion: Some(now + chrono::Duration::seconds(10)),
-            recipe: Some(Recipe {
-                item_id: ItemId("CHR"),
-                batch_duration: chrono::TimeDelta::seconds(10),
-                batch_size: 2,
-                inputs: None,
-            }),
-            started_at: Some(now),
-        };
-        Self {
-            inventory: RwSignal::new(Inventory::new()),
-            buildings: Buildings::new(),
-            land: Land::new(500),
-            workers: Workers::new(),
-            production_queue: RwSignal::new([slot].to_vec()),
+        let state = Self::new();
+        state.inventory.update(|inv| {
+            inv.add_item(ItemId("LOG"), 50);
+        });
+
+        for (housing_type, amount) in [
+            (HousingType::Cheap, 1),
+            (HousingType::Standard, 2),
+            (HousingType::Fancy, 1),
+        ] {
+            let tx = Transaction::new()
+                .use_land(housing_type.details().land_used * amount)
+                .build_housing(housing_type, amount);
+            state.commit(tx).unwrap();
         }
+
+        for (production_type, recipe_id) in [
+            (ProductionType::Sawmill, RecipeId("SAW-BRD")),
+            (ProductionType::Workshop, RecipeId("WRK-CHR")),
+        ] {
+            let details = production_type.details();
+            let mut tx = Transaction::new()
+                .use_land(details.land)
+                .assign(&details.workers, 1)
+                .build_production(production_type, 1);
+            for &(worker_type, amount) in &details.workers {
+                tx = tx.hire(worker_type, amount);
+            }
+            let id = state.commit(tx).unwrap().remove(0);
+
+            let recipe = Recipe::get(recipe_id).unwrap();
+            state.buildings.production.update_building(&id, |building| {
+                building.slots = vec![ProductionSlot::new(recipe)]
+            });
+        }
+
+        state
+    }
+
+    /// Runs `f` on building `building_id` together with the tile inventory.
+    pub fn update_building<T>(
+        &self,
+        building_id: &BuildingId,
+        f: impl FnOnce(&mut ProductionBuilding, &mut Inventory) -> T,
+    ) -> Option<T> {
+        let inventory = self.inventory;
+        self.buildings
+            .production
+            .update_building(building_id, |building| {
+                inventory.try_update(|inv| f(building, inv))
+            })
+            .flatten()
+    }
+
+    /// Runs `f` on slot `index` of building `building_id` together with the tile inventory.
+    pub fn update_slot<T>(
+        &self,
+        building_id: &BuildingId,
+        index: usize,
+        f: impl FnOnce(&mut ProductionSlot, &mut Inventory) -> T,
+    ) -> Option<T> {
+        self.update_building(building_id, |building, inv| {
+            building.slots.get_mut(index).map(|slot| f(slot, inv))
+        })
+        .flatten()
+    }
+}
+
+impl Tile<'_> {
+    pub fn hex(&self) -> Hex {
+        Hex::from_offset(self.row, self.col)
     }
 }
 
@@ -64,15 +126,35 @@ impl Tile<'static> {
         Self {
             id: "",
             description: "",
-            resources: &[],
-            price: 0.,
+            price: Money::ZERO,
             is_owned: RwSignal::new(false),
             row: 0,
             col: 0,
+            terrain: Terrain::Plains,
             tile_state: TileState::new(),
         }
     }
 
+    /// Units of `kind` the running extractors take every `PAYROLL_PERIOD`, see
+    /// `sim::extraction_rate`.
+    pub fn extraction_rate(&self, kind: ResourceKind) -> u64 {
+        let resources = self.tile_state.resources.get();
+        self.tile_state
+            .buildings
+            .production
+            .buildings
+            .with(|buildings| sim::extraction_rate(buildings, &resources, kind))
+    }
+
+    /// Where the deposit of `kind` is heading at the current extraction rate.
+    pub fn depletion_forecast(&self, kind: ResourceKind) -> Forecast {
+        let deposit = self
+            .tile_state
+            .resources
+            .with(|resources| resources.get(kind));
+        deposit.forecast(kind, self.extraction_rate(kind))
+    }
+
     pub fn hired_workers(&self, worker_type: WorkerType) -> u64 {
         self.tile_state.workers.get_total(worker_type)
     }
@@ -84,121 +166,177 @@ impl Tile<'static> {
     pub fn hire_workers(
         &self,
         worker_type: WorkerType,
-        money: RwSignal<f64>,
+        money: RwSignal<Money>,
         amount: u64,
-    ) -> Result<(), String> {
-        let cost = worker_type.details().cost;
-        let total_cost = cost * amount as f64;
-
-        if self.workers_can_accommodate(worker_type) <= amount {
-            return Err(format!(
-                "Cannot hire {} workers, only space for {}.",
-                amount,
-                self.workers_can_accommodate(worker_type)
-            ));
-        }
-
-        if money.get() < total_cost {
-            return Err(format!("Insufficient funds. Need ${}.", total_cost));
-        }
-
-        money.update(|m| *m -= total_cost);
-        self.tile_state.workers.hire(worker_type, amount);
+    ) -> Result<(), GameError> {
+        let total_cost = worker_type.details().cost * amount;
+        let tx = Transaction::new()
+            .pay(money, total_cost)
+            .hire(worker_type, amount);
+        self.tile_state.commit(tx)?;
         Ok(())
     }
 
     pub fn owned_housing(&self, housing_type: HousingType) -> u64 {
-        let housing = self.tile_state.buildings.housing;
-        match housing_type {
-            HousingType::Cheap => housing.cheap.get(),
-            HousingType::Standard => housing.standard.get(),
-            HousingType::Fancy => housing.fancy.get(),
-        }
+        self.tile_state.buildings.housing.count(housing_type)
     }
 
     pub fn build_housing(
         &self,
         housing_type: HousingType,
-        money: RwSignal<f64>,
+        money: RwSignal<Money>,
         amount: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), GameError> {
         let details = housing_type.details();
-        let total_cost = details.cost * amount as f64;
-        let total_land = details.land_used * amount;
-
-        if money.get() < total_cost {
-            return Err(format!("Insufficient funds. Need ${}.", total_cost));
-        }
-
-        self.tile_state.land.use_land(total_land)?;
-
-        money.update(|m| *m -= total_cost);
-        self.tile_state
-            .buildings
-            .housing
-            .build(housing_type, amount);
+        let tx = Transaction::new()
+            .pay(money, details.cost * amount)
+            .use_land(details.land_used * amount)
+            .build_housing(housing_type, amount);
+        self.tile_state.commit(tx)?;
         Ok(())
     }
 
-    pub fn destroy_housing(&self, housing_type: HousingType, amount: u64) -> Result<(), String> {
+    /// Demolishes housing, firing unassigned workers that would no longer have a
+    /// home. Refuses if assigned workers would be left homeless. Returns how many
+    /// workers were evicted.
+    pub fn destroy_housing(
+        &self,
+        housing_type: HousingType,
+        amount: u64,
+    ) -> Result<u64, GameError> {
         let details = housing_type.details();
-        let total_land = details.land_used * amount;
+        let worker_type = housing_type.worker_type();
+        let workers = self.tile_state.workers;
 
-        // Should only fail on bad implementation, not in client, surely
-        self.tile_state.land.free_land(total_land)?;
-        self.tile_state
-            .buildings
-            .housing
-            .destroy(housing_type, amount)?;
+        let capacity = self
+            .workers_can_accommodate(worker_type)
+            .saturating_sub(details.accomodates.0 * amount);
+        let evicted = workers
+            .get_total(worker_type)
+            .saturating_sub(capacity)
+            .min(workers.get_available(worker_type));
+
+        let tx = Transaction::new()
+            .fire(worker_type, evicted)
+            .destroy_housing(housing_type, amount)
+            .free_land(details.land_used * amount);
+        self.tile_state.commit(tx)?;
+        Ok(evicted)
+    }
+
+    pub fn fire_workers(&self, worker_type: WorkerType, amount: u64) -> Result<(), GameError> {
+        let tx = Transaction::new().fire(worker_type, amount);
+        self.tile_state.commit(tx)?;
         Ok(())
     }
 
     pub fn owned_production_buildings(&self, production_type: ProductionType) -> u64 {
-        let production = self.tile_state.buildings.production;
-
-        match production_type {
-            ProductionType::Warehouse => production.warehouse.get(),
-            ProductionType::Sawmill => production.sawmill.get(),
-            ProductionType::WaterPump => production.water_pump.get(),
-            ProductionType::Workshop => production.workshop.get(),
-        }
+        self.tile_state.buildings.production.count(production_type)
     }
 
-    // pub fn get_production(&self) -> impl Iterator {
-    //     self.tile_state.buildings.production.get_all()
-    // }
-
     pub fn build_production(
         &self,
         production_type: ProductionType,
-        money: RwSignal<f64>,
+        money: RwSignal<Money>,
         amount: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), GameError> {
         let details = production_type.details();
-        let total_cost = details.cost * amount as f64;
-        let total_land = details.land * amount;
-
-        if money.get() < total_cost {
-            return Err(format!("Insufficient funds. Need ${:.2}.", total_cost));
+        if let Some(resource) = details.extracts {
+            if self
+                .tile_state
+                .resources
+                .with_untracked(|resources| resources.abundance(resource))
+                == 0
+            {
+                return Err(GameError::NoDeposit(resource));
+            }
         }
+        let tx = Transaction::new()
+            .pay(money, details.cost * amount)
+            .use_land(details.land * amount)
+            .assign(&details.workers, amount)
+            .build_production(production_type, amount);
+        self.tile_state.commit(tx)?;
+        Ok(())
+    }
 
-        if self.tile_state.land.available.get() < total_land {
-            return Err(format!(
-                "Insufficient land. Need {}, have {}.",
-                total_land,
-                self.tile_state.land.available.get()
-            ));
+    /// Demolishes a building: frees its land, releases its workers, cancels its
+    /// slots refunding the inputs of batches in progress, and pays back
+    /// `SALVAGE_PERCENT` of the cost and upgrades invested in it. Returns the
+    /// salvage paid.
+    pub fn destroy_production(
+        &self,
+        building_id: &BuildingId,
+        money: RwSignal<Money>,
+    ) -> Result<Money, GameError> {
+        let production = self.tile_state.buildings.production;
+        let building = production
+            .get_untracked(building_id)
+            .ok_or(GameError::BuildingNotFound)?;
+        let details = building.production_type.details();
+        let salvage = building.invested().percent(SALVAGE_PERCENT);
+
+        let mut tx = Transaction::new()
+            .refund(money, salvage)
+            .free_land(details.land)
+            .remove_production(building_id.clone());
+        // Idle buildings already released their workers.
+        if !building.is_idle() {
+            tx = tx.unassign(&building.workers);
+        }
+        for slot in &building.slots {
+            tx = tx.store(slot.inputs_in_progress());
         }
+        self.tile_state.commit(tx)?;
+        Ok(salvage)
+    }
 
-        self.tile_state.workers.check_assign(&details.workers)?;
+    /// Idles a building, freezing its production and releasing its workers, or
+    /// reactivates it if the workers can be assigned again.
+    pub fn set_production_idle(
+        &self,
+        building_id: &BuildingId,
+        idle: bool,
+    ) -> Result<(), GameError> {
+        let building = self
+            .tile_state
+            .buildings
+            .production
+            .get_untracked(building_id)
+            .ok_or(GameError::BuildingNotFound)?;
+        if idle == building.is_idle() {
+            return Ok(());
+        }
 
-        self.tile_state.land.use_land(total_land).unwrap();
+        let now = Utc::now();
+        let tx = if idle {
+            Transaction::new()
+                .unassign(&building.workers)
+                .idle_production(building_id.clone(), now)
+        } else {
+            Transaction::new()
+                .assign(&building.workers, 1)
+                .activate_production(building_id.clone(), now)
+        };
+        self.tile_state.commit(tx)?;
+        Ok(())
+    }
 
-        self.tile_state
+    pub fn upgrade_production(
+        &self,
+        building_id: &BuildingId,
+        money: RwSignal<Money>,
+    ) -> Result<(), GameError> {
+        let building = self
+            .tile_state
             .buildings
             .production
-            .build(production_type, amount);
-        money.update(|m| *m -= total_cost);
+            .get_untracked(building_id)
+            .ok_or(GameError::BuildingNotFound)?;
+        let tx = Transaction::new()
+            .pay(money, building.upgrade_cost())
+            .upgrade_production(building_id.clone());
+        self.tile_state.commit(tx)?;
         Ok(())
     }
 }
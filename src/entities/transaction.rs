@@ -0,0 +1,676 @@
+use chrono::{DateTime, Utc};
+use leptos::prelude::*;
+
+use crate::entities::production::MAX_BUILDING_LEVEL;
+use crate::entities::tile::TileState;
+use crate::entities::{
+    BuildingId, GameError, HousingType, ItemDetails, ItemId, Money, ProductionBuilding,
+    ProductionType, WorkerType,
+};
+
+/// A set of changes to a tile and the player's cash. `TileState::commit` checks
+/// every change against the current state first and only then applies them, so
+/// a failed action never leaves money spent, land used, workers assigned or
+/// items lost.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transaction {
+    money: Option<RwSignal<Money>>,
+    pay: Money,
+    refund: Money,
+    use_land: u64,
+    free_land: u64,
+    hire: Vec<(WorkerType, u64)>,
+    fire: Vec<(WorkerType, u64)>,
+    assign: Vec<(WorkerType, u64)>,
+    unassign: Vec<(WorkerType, u64)>,
+    build_housing: Vec<(HousingType, u64)>,
+    destroy_housing: Vec<(HousingType, u64)>,
+    build_production: Vec<(ProductionType, u64)>,
+    remove_production: Vec<BuildingId>,
+    idle_production: Vec<(BuildingId, DateTime<Utc>)>,
+    activate_production: Vec<(BuildingId, DateTime<Utc>)>,
+    upgrade_production: Vec<BuildingId>,
+    store: Vec<(ItemId, u64)>,
+}
+
+fn sum<T: PartialEq>(changes: &[(T, u64)], kind: T) -> u64 {
+    changes
+        .iter()
+        .filter(|(k, _)| *k == kind)
+        .map(|&(_, amount)| amount)
+        .sum()
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All payments and refunds of a transaction go to one cash signal.
+    fn set_money(&mut self, money: RwSignal<Money>) {
+        debug_assert!(
+            self.money.is_none_or(|current| current == money),
+            "a transaction pays from a single cash signal"
+        );
+        self.money = Some(money);
+    }
+
+    pub fn pay(mut self, money: RwSignal<Money>, amount: Money) -> Self {
+        self.set_money(money);
+        self.pay += amount;
+        self
+    }
+
+    pub fn refund(mut self, money: RwSignal<Money>, amount: Money) -> Self {
+        self.set_money(money);
+        self.refund += amount;
+        self
+    }
+
+    pub fn use_land(mut self, amount: u64) -> Self {
+        self.use_land += amount;
+        self
+    }
+
+    pub fn free_land(mut self, amount: u64) -> Self {
+        self.free_land += amount;
+        self
+    }
+
+    pub fn hire(mut self, worker_type: WorkerType, amount: u64) -> Self {
+        self.hire.push((worker_type, amount));
+        self
+    }
+
+    pub fn fire(mut self, worker_type: WorkerType, amount: u64) -> Self {
+        self.fire.push((worker_type, amount));
+        self
+    }
+
+    /// Assigns `workers` once for each of `times` buildings.
+    pub fn assign(mut self, workers: &[(WorkerType, u64)], times: u64) -> Self {
+        self.assign
+            .extend(workers.iter().map(|&(kind, amount)| (kind, amount * times)));
+        self
+    }
+
+    pub fn unassign(mut self, workers: &[(WorkerType, u64)]) -> Self {
+        self.unassign.extend_from_slice(workers);
+        self
+    }
+
+    pub fn build_housing(mut self, housing_type: HousingType, amount: u64) -> Self {
+        self.build_housing.push((housing_type, amount));
+        self
+    }
+
+    pub fn destroy_housing(mut self, housing_type: HousingType, amount: u64) -> Self {
+        self.destroy_housing.push((housing_type, amount));
+        self
+    }
+
+    pub fn build_production(mut self, production_type: ProductionType, amount: u64) -> Self {
+        self.build_production.push((production_type, amount));
+        self
+    }
+
+    pub fn remove_production(mut self, building_id: BuildingId) -> Self {
+        self.remove_production.push(building_id);
+        self
+    }
+
+    pub fn idle_production(mut self, building_id: BuildingId, now: DateTime<Utc>) -> Self {
+        self.idle_production.push((building_id, now));
+        self
+    }
+
+    pub fn activate_production(mut self, building_id: BuildingId, now: DateTime<Utc>) -> Self {
+        self.activate_production.push((building_id, now));
+        self
+    }
+
+    pub fn upgrade_production(mut self, building_id: BuildingId) -> Self {
+        self.upgrade_production.push(building_id);
+        self
+    }
+
+    /// Adds `items` to the tile inventory, all of them or the transaction fails.
+    pub fn store(mut self, items: &[(ItemId, u64)]) -> Self {
+        self.store.extend_from_slice(items);
+        self
+    }
+}
+
+impl TileState {
+    /// Checks that `tx` can be applied as a whole.
+    pub fn validate(&self, tx: &Transaction) -> Result<(), GameError> {
+        let need = tx.pay - tx.refund;
+        let have = tx.money.map_or(Money::ZERO, |money| money.get_untracked());
+        if need > have {
+            return Err(GameError::InsufficientFunds { need, have });
+        }
+
+        let available = self.land.available.get_untracked();
+        let used = self.land.total.get_untracked() - available;
+        if tx.use_land > available + tx.free_land {
+            return Err(GameError::InsufficientLand {
+                need: tx.use_land,
+                available: available + tx.free_land,
+            });
+        }
+        if tx.free_land > used {
+            return Err(GameError::LandNotInUse {
+                freeing: tx.free_land,
+                used,
+            });
+        }
+
+        let housing = self.buildings.housing;
+        for housing_type in HousingType::all() {
+            let requested = sum(&tx.destroy_housing, housing_type);
+            let owned = housing.count(housing_type);
+            if requested > owned {
+                return Err(GameError::NotEnoughHousing {
+                    housing_type,
+                    requested,
+                    owned,
+                });
+            }
+        }
+
+        for worker_type in WorkerType::all() {
+            self.validate_workers(tx, worker_type)?;
+        }
+
+        let production = self.buildings.production;
+        let changed = tx
+            .remove_production
+            .iter()
+            .chain(tx.idle_production.iter().map(|(id, _)| id))
+            .chain(tx.activate_production.iter().map(|(id, _)| id));
+        for building_id in changed {
+            if !production.contains(building_id) {
+                return Err(GameError::BuildingNotFound);
+            }
+        }
+        for building_id in &tx.upgrade_production {
+            let level = production
+                .get_untracked(building_id)
+                .ok_or(GameError::BuildingNotFound)?
+                .level;
+            if level >= MAX_BUILDING_LEVEL {
+                return Err(GameError::MaxLevel { level });
+            }
+        }
+
+        let (mut weight, mut volume) = self.inventory.with_untracked(|inv| {
+            (
+                inv.max_weight
+                    .get_untracked()
+                    .saturating_sub(inv.weight.get_untracked()),
+                inv.max_volume
+                    .get_untracked()
+                    .saturating_sub(inv.volume.get_untracked()),
+            )
+        });
+        for &(item_id, requested) in &tx.store {
+            let details = ItemDetails::get(item_id).ok_or(GameError::UnknownItem(item_id))?;
+            let fits = (weight / details.weight).min(volume / details.volume);
+            if fits < requested {
+                return Err(GameError::InventoryFull {
+                    item_id,
+                    requested,
+                    fits,
+                });
+            }
+            weight -= details.weight * requested;
+            volume -= details.volume * requested;
+        }
+        Ok(())
+    }
+
+    fn validate_workers(&self, tx: &Transaction, worker_type: WorkerType) -> Result<(), GameError> {
+        let hired = self.workers.get_total(worker_type);
+        let assigned = hired - self.workers.get_available(worker_type);
+        let (hire, fire) = (sum(&tx.hire, worker_type), sum(&tx.fire, worker_type));
+        let (assign, unassign) = (sum(&tx.assign, worker_type), sum(&tx.unassign, worker_type));
+
+        if fire > hired {
+            return Err(GameError::NotEnoughWorkers {
+                worker_type,
+                requested: fire,
+                hired,
+            });
+        }
+        if unassign > assigned {
+            return Err(GameError::WorkersNotAssigned {
+                worker_type,
+                requested: unassign,
+                assigned,
+            });
+        }
+
+        let capacity_change = |changes: &[(HousingType, u64)]| -> u64 {
+            changes
+                .iter()
+                .filter(|(kind, _)| kind.worker_type() == worker_type)
+                .map(|(kind, amount)| kind.details().accomodates.0 * amount)
+                .sum()
+        };
+        let capacity_before = self.buildings.get_capacity(worker_type);
+        let capacity = (capacity_before + capacity_change(&tx.build_housing))
+            .saturating_sub(capacity_change(&tx.destroy_housing));
+        let staying = hired - fire;
+        let total = staying + hire;
+        // Only refuse if the change leaves more workers without a home than before.
+        if total.saturating_sub(capacity) > hired.saturating_sub(capacity_before) {
+            return Err(if hire > 0 {
+                GameError::NoHousingCapacity {
+                    worker_type,
+                    requested: hire,
+                    space: capacity.saturating_sub(staying),
+                }
+            } else {
+                GameError::WorkersHomeless {
+                    worker_type,
+                    homeless: total - capacity,
+                }
+            });
+        }
+
+        let still_assigned = assigned - unassign;
+        if still_assigned + assign > total {
+            return Err(if assign > 0 {
+                GameError::WorkersUnavailable {
+                    worker_type,
+                    need: assign,
+                    available: total.saturating_sub(still_assigned),
+                }
+            } else {
+                GameError::WorkersAssigned {
+                    worker_type,
+                    requested: fire,
+                    unassigned: hired - still_assigned,
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Validates `tx` and applies every change in it, or none if it is invalid.
+    /// Returns the ids of the production buildings it built.
+    pub fn commit(&self, tx: Transaction) -> Result<Vec<BuildingId>, GameError> {
+        self.validate(&tx)?;
+
+        // Everything below was checked by `validate`, the order only matters so
+        // that no intermediate step dips below zero.
+        if let Some(money) = tx.money {
+            money.update(|m| *m = *m + tx.refund - tx.pay);
+        }
+        let production = self.buildings.production;
+        for building_id in &tx.remove_production {
+            production.remove(building_id);
+        }
+        for (building_id, now) in &tx.idle_production {
+            production.update_building(building_id, |building| building.set_idle(*now));
+        }
+        for (building_id, now) in &tx.activate_production {
+            production.update_building(building_id, |building| building.set_active(*now));
+        }
+        for building_id in &tx.upgrade_production {
+            production.update_building(building_id, ProductionBuilding::upgrade);
+        }
+        if !tx.store.is_empty() {
+            self.inventory.update(|inv| {
+                for &(item_id, quantity) in &tx.store {
+                    inv.add_item(item_id, quantity);
+                }
+            });
+        }
+        for &(worker_type, amount) in &tx.hire {
+            self.workers.hire(worker_type, amount);
+        }
+        for &(worker_type, amount) in &tx.unassign {
+            self.workers.unassign(worker_type, amount)?;
+        }
+        for &(worker_type, amount) in &tx.fire {
+            self.workers.fire(worker_type, amount)?;
+        }
+        for &(worker_type, amount) in &tx.assign {
+            self.workers.assign(worker_type, amount)?;
+        }
+        for &(housing_type, amount) in &tx.destroy_housing {
+            self.buildings.housing.destroy(housing_type, amount)?;
+        }
+        for &(housing_type, amount) in &tx.build_housing {
+            self.buildings.housing.build(housing_type, amount);
+        }
+        self.land.free_land(tx.free_land)?;
+        self.land.use_land(tx.use_land)?;
+
+        Ok(tx
+            .build_production
+            .iter()
+            .flat_map(|&(production_type, amount)| production.build(production_type, amount))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::production::SALVAGE_PERCENT;
+    use crate::entities::{
+        test_owner, ProductionSlot, Recipe, RecipeId, ResourceKind, ResourceMap, Tile,
+    };
+
+    /// A tile with water, so Water Pumps can be built.
+    fn tile() -> Tile<'static> {
+        let tile = Tile::new();
+        tile.tile_state
+            .resources
+            .set(ResourceMap::new(&[(ResourceKind::Water, 50)]));
+        tile
+    }
+
+    fn housed_tile(housing_type: HousingType, amount: u64) -> Tile<'static> {
+        let tile = tile();
+        let tx = Transaction::new()
+            .use_land(housing_type.details().land_used * amount)
+            .build_housing(housing_type, amount);
+        tile.tile_state.commit(tx).unwrap();
+        tile
+    }
+
+    #[test]
+    fn hiring_can_fill_the_last_housing_slot() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(10_000));
+
+        tile.hire_workers(WorkerType::Basic, money, 10).unwrap();
+        assert_eq!(tile.hired_workers(WorkerType::Basic), 10);
+
+        let err = tile.hire_workers(WorkerType::Basic, money, 1).unwrap_err();
+        assert_eq!(
+            err,
+            GameError::NoHousingCapacity {
+                worker_type: WorkerType::Basic,
+                requested: 1,
+                space: 0,
+            }
+        );
+        assert_eq!(
+            money.get(),
+            Money::dollars(10_000) - WorkerType::Basic.details().cost * 10
+        );
+    }
+
+    #[test]
+    fn failed_build_changes_nothing() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(10_000));
+        tile.hire_workers(WorkerType::Basic, money, 5).unwrap();
+        let cash = money.get();
+        let land = tile.tile_state.land.available.get();
+
+        // The Sawmill also needs Advanced workers, none are hired.
+        let err = tile
+            .build_production(ProductionType::Sawmill, money, 1)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            GameError::WorkersUnavailable {
+                worker_type: WorkerType::Advanced,
+                ..
+            }
+        ));
+        assert_eq!(money.get(), cash);
+        assert_eq!(tile.tile_state.land.available.get(), land);
+        assert_eq!(tile.tile_state.workers.get_available(WorkerType::Basic), 5);
+        assert_eq!(tile.owned_production_buildings(ProductionType::Sawmill), 0);
+    }
+
+    #[test]
+    fn insufficient_funds_are_reported_before_anything_changes() {
+        let _owner = test_owner();
+        let tile = tile();
+        let money = RwSignal::new(Money::dollars(100));
+
+        let err = tile
+            .build_production(ProductionType::Warehouse, money, 1)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            GameError::InsufficientFunds {
+                need: Money::dollars(600),
+                have: Money::dollars(100),
+            }
+        );
+        assert_eq!(tile.tile_state.land.used(), 0);
+    }
+
+    #[test]
+    fn insufficient_land_leaves_workers_unassigned() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(100_000));
+        tile.hire_workers(WorkerType::Basic, money, 6).unwrap();
+        tile.tile_state
+            .land
+            .use_land(tile.tile_state.land.available.get())
+            .unwrap();
+
+        let err = tile
+            .build_production(ProductionType::WaterPump, money, 1)
+            .unwrap_err();
+        assert!(matches!(err, GameError::InsufficientLand { .. }));
+        assert_eq!(tile.tile_state.workers.get_available(WorkerType::Basic), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "single cash signal")]
+    fn paying_from_two_cash_signals_is_refused() {
+        let _owner = test_owner();
+        let _ = Transaction::new()
+            .pay(RwSignal::new(Money::dollars(10)), Money::dollars(1))
+            .refund(RwSignal::new(Money::dollars(10)), Money::dollars(1));
+    }
+
+    #[test]
+    fn demolishing_housing_evicts_unassigned_workers() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 2);
+        let money = RwSignal::new(Money::dollars(100_000));
+        tile.hire_workers(WorkerType::Basic, money, 15).unwrap();
+
+        assert_eq!(tile.destroy_housing(HousingType::Cheap, 1), Ok(5));
+        assert_eq!(tile.hired_workers(WorkerType::Basic), 10);
+        assert_eq!(tile.owned_housing(HousingType::Cheap), 1);
+    }
+
+    #[test]
+    fn demolishing_housing_refuses_to_leave_assigned_workers_homeless() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(100_000));
+        tile.hire_workers(WorkerType::Basic, money, 6).unwrap();
+        tile.build_production(ProductionType::WaterPump, money, 1)
+            .unwrap();
+        let land = tile.tile_state.land.available.get();
+
+        assert_eq!(
+            tile.destroy_housing(HousingType::Cheap, 1),
+            Err(GameError::WorkersHomeless {
+                worker_type: WorkerType::Basic,
+                homeless: 6,
+            })
+        );
+        assert_eq!(tile.owned_housing(HousingType::Cheap), 1);
+        assert_eq!(tile.hired_workers(WorkerType::Basic), 6);
+        assert_eq!(tile.tile_state.land.available.get(), land);
+    }
+
+    #[test]
+    fn assigned_workers_cannot_be_fired() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(100_000));
+        tile.hire_workers(WorkerType::Basic, money, 7).unwrap();
+        tile.build_production(ProductionType::WaterPump, money, 1)
+            .unwrap();
+
+        tile.fire_workers(WorkerType::Basic, 1).unwrap();
+        assert_eq!(
+            tile.fire_workers(WorkerType::Basic, 1),
+            Err(GameError::WorkersAssigned {
+                worker_type: WorkerType::Basic,
+                requested: 1,
+                unassigned: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn demolishing_production_releases_everything() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(100_000));
+        tile.hire_workers(WorkerType::Basic, money, 6).unwrap();
+        let land = tile.tile_state.land.available.get();
+        tile.build_production(ProductionType::WaterPump, money, 1)
+            .unwrap();
+        let cash = money.get();
+
+        let id = tile
+            .tile_state
+            .buildings
+            .production
+            .buildings
+            .with(|buildings| buildings[0].id.clone());
+        let salvage = tile.destroy_production(&id, money).unwrap();
+
+        assert_eq!(money.get(), cash + salvage);
+        assert_eq!(tile.tile_state.land.available.get(), land);
+        assert_eq!(tile.tile_state.workers.get_available(WorkerType::Basic), 6);
+        assert_eq!(
+            tile.destroy_production(&id, money),
+            Err(GameError::BuildingNotFound)
+        );
+    }
+
+    #[test]
+    fn demolishing_salvages_upgrades_too() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(100_000));
+        tile.hire_workers(WorkerType::Basic, money, 6).unwrap();
+        let cash = money.get();
+        tile.build_production(ProductionType::WaterPump, money, 1)
+            .unwrap();
+        let id = tile
+            .tile_state
+            .buildings
+            .production
+            .buildings
+            .with(|buildings| buildings[0].id.clone());
+        tile.upgrade_production(&id, money).unwrap();
+        tile.upgrade_production(&id, money).unwrap();
+        let spent = cash - money.get();
+
+        let salvage = tile.destroy_production(&id, money).unwrap();
+        assert_eq!(salvage, spent.percent(SALVAGE_PERCENT));
+        assert_eq!(
+            salvage,
+            (ProductionType::WaterPump.details().cost * 4).percent(SALVAGE_PERCENT)
+        );
+    }
+
+    #[test]
+    fn demolishing_refuses_to_destroy_refunded_inputs() {
+        let _owner = test_owner();
+        let tile = tile();
+        let money = RwSignal::new(Money::ZERO);
+        let land = ProductionType::Sawmill.details().land;
+        tile.tile_state.land.use_land(land).unwrap();
+        // Idle, so it holds no workers.
+        let mut sawmill = ProductionBuilding::new(ProductionType::Sawmill);
+        sawmill.idle_since = Some(Utc::now());
+        sawmill.slots = vec![ProductionSlot::new(
+            Recipe::get(RecipeId("SAW-BRD")).unwrap(),
+        )];
+        sawmill.slots[0].next_completion = Some(Utc::now());
+        let id = sawmill.id.clone();
+        let production = tile.tile_state.buildings.production;
+        production
+            .buildings
+            .update(|buildings| buildings.push(sawmill));
+
+        let inventory = tile.tile_state.inventory;
+        inventory.with(|inv| inv.max_weight.set(0));
+        assert_eq!(
+            tile.destroy_production(&id, money),
+            Err(GameError::InventoryFull {
+                item_id: ItemId("LOG"),
+                requested: 1,
+                fits: 0,
+            })
+        );
+        assert!(production.contains(&id));
+        assert_eq!(money.get(), Money::ZERO);
+        assert_eq!(tile.tile_state.land.used(), land);
+
+        inventory.with(|inv| inv.max_weight.set(500_000));
+        tile.destroy_production(&id, money).unwrap();
+        assert!(!production.contains(&id));
+        assert_eq!(inventory.with(|inv| inv.quantity(ItemId("LOG"))), 1);
+    }
+
+    #[test]
+    fn idling_round_trips_worker_assignment() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(100_000));
+        tile.hire_workers(WorkerType::Basic, money, 6).unwrap();
+        tile.build_production(ProductionType::WaterPump, money, 1)
+            .unwrap();
+        let id = tile
+            .tile_state
+            .buildings
+            .production
+            .buildings
+            .with(|buildings| buildings[0].id.clone());
+
+        tile.set_production_idle(&id, true).unwrap();
+        assert_eq!(tile.tile_state.workers.get_available(WorkerType::Basic), 6);
+        tile.fire_workers(WorkerType::Basic, 1).unwrap();
+
+        assert!(matches!(
+            tile.set_production_idle(&id, false),
+            Err(GameError::WorkersUnavailable { .. })
+        ));
+        assert_eq!(tile.tile_state.workers.get_available(WorkerType::Basic), 5);
+    }
+
+    #[test]
+    fn extractors_need_their_resource() {
+        let _owner = test_owner();
+        let tile = housed_tile(HousingType::Cheap, 1);
+        let money = RwSignal::new(Money::dollars(10_000));
+        tile.hire_workers(WorkerType::Basic, money, 8).unwrap();
+
+        assert_eq!(
+            tile.build_production(ProductionType::LoggingCamp, money, 1),
+            Err(GameError::NoDeposit(ResourceKind::Wood))
+        );
+        assert_eq!(
+            tile.owned_production_buildings(ProductionType::LoggingCamp),
+            0
+        );
+        assert_eq!(
+            money.get(),
+            Money::dollars(10_000) - WorkerType::Basic.details().cost * 8
+        );
+    }
+}
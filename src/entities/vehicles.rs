@@ -0,0 +1,214 @@
+use chrono::Duration;
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entities::transaction::Transaction;
+use crate::entities::{GameError, GameState, Inventory, Money, Shipment, Tile};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum VehicleType {
+    Cart,
+    Truck,
+    Barge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VehicleDetails {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub cost: Money,
+    pub upkeep: Money,
+    /// In the units of `ItemDetails::weight`.
+    pub max_weight: u64,
+    /// In the units of `ItemDetails::volume`.
+    pub max_volume: u64,
+    /// Time to cross one hex of plains, rougher terrain takes its
+    /// `move_cost` times as long.
+    pub time_per_hex: Duration,
+}
+
+impl VehicleType {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Cart, Self::Truck, Self::Barge]
+    }
+
+    pub fn details(&self) -> VehicleDetails {
+        match self {
+            VehicleType::Cart => VehicleDetails {
+                name: "Cart",
+                description: "A horse and a cart. Slow, but it gets there.",
+                cost: Money::dollars(800),
+                upkeep: Money::from_cents(50),
+                max_weight: 25_000,
+                max_volume: 25_000,
+                time_per_hex: Duration::minutes(2),
+            },
+            VehicleType::Truck => VehicleDetails {
+                name: "Truck",
+                description: "Carries a lot, quickly.",
+                cost: Money::dollars(6_000),
+                upkeep: Money::dollars(3),
+                max_weight: 150_000,
+                max_volume: 150_000,
+                time_per_hex: Duration::seconds(40),
+            },
+            VehicleType::Barge => VehicleDetails {
+                name: "Barge",
+                description: "Moves whole warehouses at walking pace.",
+                cost: Money::dollars(15_000),
+                upkeep: Money::dollars(5),
+                max_weight: 600_000,
+                max_volume: 600_000,
+                time_per_hex: Duration::seconds(90),
+            },
+        }
+    }
+
+    /// Time to travel a route of the given `cost`, see `GameState::route`.
+    pub fn travel_time(&self, cost: u32) -> Duration {
+        self.details().time_per_hex * cost as i32
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct VehicleId(pub String);
+
+impl VehicleId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// First segment of the id, enough to tell vehicles apart in the UI.
+    pub fn short(&self) -> &str {
+        self.0.split('-').next().unwrap_or(&self.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum VehicleLocation {
+    /// Standing at a tile, where it can be loaded and unloaded.
+    Parked(String),
+    EnRoute(Shipment),
+}
+
+impl VehicleLocation {
+    pub fn parked_at(&self) -> Option<&str> {
+        match self {
+            Self::Parked(tile_id) => Some(tile_id),
+            Self::EnRoute(_) => None,
+        }
+    }
+
+    /// Tile the vehicle is at, or the one it is headed to.
+    pub fn tile_id(&self) -> &str {
+        match self {
+            Self::Parked(tile_id) => tile_id,
+            Self::EnRoute(shipment) => &shipment.to,
+        }
+    }
+}
+
+/// A vehicle of the player's fleet. Its cargo is an `Inventory` sized to the
+/// vehicle, so it can be loaded like any other storage.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vehicle {
+    pub id: VehicleId,
+    pub vehicle_type: VehicleType,
+    pub inventory: RwSignal<Inventory>,
+    pub location: RwSignal<VehicleLocation>,
+}
+
+impl Vehicle {
+    pub fn new(id: VehicleId, vehicle_type: VehicleType, location: VehicleLocation) -> Self {
+        let details = vehicle_type.details();
+        let inventory = Inventory::new();
+        inventory.max_weight.set(details.max_weight);
+        inventory.max_volume.set(details.max_volume);
+        Self {
+            inventory: RwSignal::new(Inventory {
+                name: format!("{} {}", details.name, id.short()),
+                ..inventory
+            }),
+            id,
+            vehicle_type,
+            location: RwSignal::new(location),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{} {}", self.vehicle_type.details().name, self.id.short())
+    }
+}
+
+impl GameState {
+    pub fn vehicle(&self, vehicle_id: &VehicleId) -> Option<Vehicle> {
+        self.vehicles.with_untracked(|vehicles| {
+            vehicles
+                .iter()
+                .find(|vehicle| vehicle.id == *vehicle_id)
+                .cloned()
+        })
+    }
+
+    /// Buys a vehicle and parks it at `tile`.
+    pub fn buy_vehicle(
+        &self,
+        tile: &Tile<'static>,
+        vehicle_type: VehicleType,
+    ) -> Result<Vehicle, GameError> {
+        if !tile.is_owned.get_untracked() {
+            return Err(GameError::TileNotOwned);
+        }
+        tile.tile_state
+            .commit(Transaction::new().pay(self.cash, vehicle_type.details().cost))?;
+
+        let vehicle = Vehicle::new(
+            VehicleId::new(),
+            vehicle_type,
+            VehicleLocation::Parked(tile.id.to_string()),
+        );
+        self.vehicles
+            .update(|vehicles| vehicles.push(vehicle.clone()));
+        Ok(vehicle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_owner;
+
+    #[test]
+    fn bought_vehicles_are_parked_and_paid_for() {
+        let _owner = test_owner();
+        let mut game_state = GameState::new();
+        let tile = Tile {
+            id: "A",
+            ..Tile::new()
+        };
+        game_state.tiles.push(tile);
+
+        assert_eq!(
+            game_state.buy_vehicle(&tile, VehicleType::Cart),
+            Err(GameError::TileNotOwned)
+        );
+        tile.is_owned.set(true);
+        let truck = game_state.buy_vehicle(&tile, VehicleType::Truck).unwrap();
+
+        assert_eq!(game_state.cash.get(), Money::dollars(10_000 - 6_000));
+        assert_eq!(
+            truck.location.get(),
+            VehicleLocation::Parked("A".to_string())
+        );
+        assert_eq!(truck.inventory.with(|inv| inv.max_weight.get()), 150_000);
+        assert_eq!(game_state.vehicle(&truck.id), Some(truck));
+        assert_eq!(
+            game_state.buy_vehicle(&tile, VehicleType::Barge),
+            Err(GameError::InsufficientFunds {
+                need: Money::dollars(15_000),
+                have: Money::dollars(4_000),
+            })
+        );
+    }
+}
@@ -1,13 +1,16 @@
 use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use crate::entities::{GameError, Money};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum WorkerType {
     Basic,
     Advanced,
     Expert,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Workers {
     pub basic: WorkerCategory,
     pub advanced: WorkerCategory,
@@ -18,7 +21,9 @@ pub struct Workers {
 pub struct WorkerDetails {
     pub name: &'static str,
     pub description: &'static str,
-    pub cost: f64,
+    pub cost: Money,
+    /// Charged per worker every `PAYROLL_PERIOD`.
+    pub wage: Money,
 }
 
 impl WorkerType {
@@ -33,78 +38,107 @@ impl WorkerType {
             WorkerType::Basic => WorkerDetails {
                 name: "Basic Worker",
                 description: "Can perform the most simple tasks.",
-                cost: 100.0,
+                cost: Money::dollars(100),
+                wage: Money::from_cents(50),
             },
             WorkerType::Advanced => WorkerDetails {
                 name: "Advanced Worker",
                 description: "Has good education and can perform more complex tasks.",
-                cost: 175.0,
+                cost: Money::dollars(175),
+                wage: Money::from_cents(100),
             },
             WorkerType::Expert => WorkerDetails {
                 name: "Expert Worker",
                 description: "Has the skills to accomplish the most complex tasks.",
-                cost: 250.0,
+                cost: Money::dollars(250),
+                wage: Money::from_cents(200),
             },
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct WorkerCategory {
+    worker_type: WorkerType,
     total: RwSignal<u64>,
     assigned: RwSignal<u64>,
 }
 
 impl WorkerCategory {
-    pub fn new() -> Self {
+    pub fn new(worker_type: WorkerType) -> Self {
         Self {
+            worker_type,
             total: RwSignal::new(0),
             assigned: RwSignal::new(0),
         }
     }
 
+    pub fn total(&self) -> u64 {
+        self.total.get()
+    }
+
+    pub fn assigned(&self) -> u64 {
+        self.assigned.get()
+    }
+
     pub fn available(&self) -> u64 {
         self.total.get() - self.assigned.get()
     }
 
+    /// Overwrites both counters, used when restoring a saved game.
+    pub(crate) fn set(&self, total: u64, assigned: u64) {
+        if self.total.get_untracked() != total {
+            self.total.set(total);
+        }
+        if self.assigned.get_untracked() != assigned {
+            self.assigned.set(assigned);
+        }
+    }
+
     pub fn hire(&self, amount: u64) {
         self.total.update(|w| *w += amount);
     }
 
-    pub fn fire(&self, amount: u64) -> Result<(), String> {
+    /// Lets go of unassigned workers. Workers staffing a building must be
+    /// released by idling or demolishing it first.
+    pub fn fire(&self, amount: u64) -> Result<(), GameError> {
         if self.total.get() < amount {
-            return Err(format!(
-                "Cannot fire {} workers, only have {}.",
-                amount,
-                self.total.get()
-            ));
+            return Err(GameError::NotEnoughWorkers {
+                worker_type: self.worker_type,
+                requested: amount,
+                hired: self.total.get(),
+            });
         }
-        if self.available() <= amount {
-            return Err("Cannot fire assigned workers.".into());
+        if self.available() < amount {
+            return Err(GameError::WorkersAssigned {
+                worker_type: self.worker_type,
+                requested: amount,
+                unassigned: self.available(),
+            });
         }
         self.total.update(|w| *w -= amount);
         Ok(())
     }
 
-    pub fn assign(&self, amount: u64) -> Result<(), String> {
+    pub fn assign(&self, amount: u64) -> Result<(), GameError> {
         if self.available() < amount {
-            return Err(format!(
-                "Cannot assign {} workers, only {} available.",
-                amount,
-                self.available()
-            ));
+            return Err(GameError::WorkersUnavailable {
+                worker_type: self.worker_type,
+                need: amount,
+                available: self.available(),
+            });
         }
         self.assigned.update(|w| *w += amount);
         Ok(())
     }
 
-    pub fn unassign(&self, amount: u64) -> Result<(), String> {
+    pub fn unassign(&self, amount: u64) -> Result<(), GameError> {
         if self.assigned.get() < amount {
-            return Err(format!(
-                "Cannot unassign {} workers, only {} assigned.",
-                amount,
-                self.assigned.get()
-            ));
+            return Err(GameError::WorkersNotAssigned {
+                worker_type: self.worker_type,
+                requested: amount,
+                assigned: self.assigned.get(),
+            });
         }
         self.assigned.update(|w| *w -= amount);
         Ok(())
@@ -114,9 +148,17 @@ impl WorkerCategory {
 impl Workers {
     pub fn new() -> Self {
         Self {
-            basic: WorkerCategory::new(),
-            advanced: WorkerCategory::new(),
-            expert: WorkerCategory::new(),
+            basic: WorkerCategory::new(WorkerType::Basic),
+            advanced: WorkerCategory::new(WorkerType::Advanced),
+            expert: WorkerCategory::new(WorkerType::Expert),
+        }
+    }
+
+    fn category(&self, worker_type: WorkerType) -> WorkerCategory {
+        match worker_type {
+            WorkerType::Basic => self.basic,
+            WorkerType::Advanced => self.advanced,
+            WorkerType::Expert => self.expert,
         }
     }
 
@@ -136,23 +178,22 @@ impl Workers {
         }
     }
 
-    pub fn assign(&self, worker_type: WorkerType, amount: u64) -> Result<(), String> {
-        match worker_type {
-            WorkerType::Basic => self.basic.assign(amount),
-            WorkerType::Advanced => self.advanced.assign(amount),
-            WorkerType::Expert => self.expert.assign(amount),
-        }
+    pub fn assign(&self, worker_type: WorkerType, amount: u64) -> Result<(), GameError> {
+        self.category(worker_type).assign(amount)
     }
 
-    pub fn check_assign(&self, workers: &[(WorkerType, u64)]) -> Result<(), String> {
+    pub fn unassign(&self, worker_type: WorkerType, amount: u64) -> Result<(), GameError> {
+        self.category(worker_type).unassign(amount)
+    }
+
+    pub fn check_assign(&self, workers: &[(WorkerType, u64)]) -> Result<(), GameError> {
         for &(worker_type, amount) in workers {
             if self.get_available(worker_type) < amount {
-                return Err(format!(
-                    "Not enough {} workers. Need {}, have {}.",
-                    worker_type.details().name,
-                    amount,
-                    self.get_available(worker_type)
-                ));
+                return Err(GameError::WorkersUnavailable {
+                    worker_type,
+                    need: amount,
+                    available: self.get_available(worker_type),
+                });
             }
         }
         for &(worker_type, amount) in workers {
@@ -168,4 +209,160 @@ impl Workers {
             WorkerType::Expert => self.expert.hire(amount),
         };
     }
+
+    pub fn fire(&self, worker_type: WorkerType, amount: u64) -> Result<(), GameError> {
+        self.category(worker_type).fire(amount)
+    }
+}
+
+impl Default for Workers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_owner;
+    use proptest::prelude::*;
+
+    fn category(total: u64, assigned: u64) -> WorkerCategory {
+        let category = WorkerCategory::new(WorkerType::Basic);
+        category.hire(total);
+        category.assign(assigned).unwrap();
+        category
+    }
+
+    #[test]
+    fn fire_can_let_go_of_every_unassigned_worker() {
+        let _owner = test_owner();
+        let workers = category(5, 2);
+        workers.fire(3).unwrap();
+        assert_eq!((workers.total(), workers.assigned()), (2, 2));
+    }
+
+    #[test]
+    fn fire_refuses_assigned_workers() {
+        let _owner = test_owner();
+        let workers = category(5, 2);
+        let err = workers.fire(4).unwrap_err();
+        assert_eq!(
+            err,
+            GameError::WorkersAssigned {
+                worker_type: WorkerType::Basic,
+                requested: 4,
+                unassigned: 3,
+            }
+        );
+        let err = workers.fire(6).unwrap_err();
+        assert_eq!(
+            err,
+            GameError::NotEnoughWorkers {
+                worker_type: WorkerType::Basic,
+                requested: 6,
+                hired: 5,
+            }
+        );
+        assert_eq!((workers.total(), workers.assigned()), (5, 2));
+    }
+
+    #[test]
+    fn assign_and_unassign_stay_within_bounds() {
+        let _owner = test_owner();
+        let workers = category(4, 0);
+        assert_eq!(
+            workers.assign(5).unwrap_err(),
+            GameError::WorkersUnavailable {
+                worker_type: WorkerType::Basic,
+                need: 5,
+                available: 4,
+            }
+        );
+        workers.assign(4).unwrap();
+        assert_eq!(
+            workers.unassign(5).unwrap_err(),
+            GameError::WorkersNotAssigned {
+                worker_type: WorkerType::Basic,
+                requested: 5,
+                assigned: 4,
+            }
+        );
+        workers.unassign(4).unwrap();
+        assert_eq!(workers.available(), 4);
+    }
+
+    #[test]
+    fn check_assign_is_all_or_nothing() {
+        let _owner = test_owner();
+        let workers = Workers::new();
+        workers.hire(WorkerType::Basic, 10);
+        workers.hire(WorkerType::Advanced, 1);
+
+        let err = workers
+            .check_assign(&[(WorkerType::Basic, 5), (WorkerType::Advanced, 2)])
+            .unwrap_err();
+        assert!(matches!(err, GameError::WorkersUnavailable { .. }));
+        assert_eq!(workers.get_available(WorkerType::Basic), 10);
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        Hire(u64),
+        Fire(u64),
+        Assign(u64),
+        Unassign(u64),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (0..20u64).prop_map(Op::Hire),
+            (0..20u64).prop_map(Op::Fire),
+            (0..20u64).prop_map(Op::Assign),
+            (0..20u64).prop_map(Op::Unassign),
+        ]
+    }
+
+    proptest! {
+        /// Random sequences keep `assigned <= total` and match a plain model.
+        /// Refused operations leave both counters untouched.
+        #[test]
+        fn operations_keep_counts_consistent(ops in prop::collection::vec(op(), 0..60)) {
+            let _owner = test_owner();
+            let workers = WorkerCategory::new(WorkerType::Expert);
+            let (mut total, mut assigned) = (0u64, 0u64);
+            for op in ops {
+                match op {
+                    Op::Hire(n) => {
+                        workers.hire(n);
+                        total += n;
+                    }
+                    Op::Fire(n) => {
+                        let ok = workers.fire(n).is_ok();
+                        prop_assert_eq!(ok, n <= total - assigned);
+                        if ok {
+                            total -= n;
+                        }
+                    }
+                    Op::Assign(n) => {
+                        let ok = workers.assign(n).is_ok();
+                        prop_assert_eq!(ok, n <= total - assigned);
+                        if ok {
+                            assigned += n;
+                        }
+                    }
+                    Op::Unassign(n) => {
+                        let ok = workers.unassign(n).is_ok();
+                        prop_assert_eq!(ok, n <= assigned);
+                        if ok {
+                            assigned -= n;
+                        }
+                    }
+                }
+                prop_assert_eq!(workers.total(), total);
+                prop_assert_eq!(workers.assigned(), assigned);
+                prop_assert!(workers.assigned() <= workers.total());
+            }
+        }
+    }
 }
@@ -0,0 +1,628 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{GameError, ItemId, Money};
+
+/// Number of recent trades kept per book.
+const RECENT_TRADES: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+impl Side {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Bid => "BUY",
+            Self::Ask => "SELL",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct OrderId(pub u64);
+
+/// What a player asks the exchange for. The escrow for it, items for an ask
+/// and `price * quantity` cash for a bid, is taken from the player's save by
+/// `place_order`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OrderRequest {
+    pub player_id: String,
+    /// Tile the escrowed items came from, or bought items are delivered to.
+    pub tile_id: String,
+    pub item_id: ItemId,
+    pub side: Side,
+    /// Limit price per unit.
+    pub price: Money,
+    pub quantity: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    pub id: OrderId,
+    pub player_id: String,
+    pub tile_id: String,
+    pub item_id: ItemId,
+    pub side: Side,
+    pub price: Money,
+    pub quantity: u64,
+    /// Units not filled yet. Their escrow is still held by the exchange.
+    pub remaining: u64,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl Order {
+    fn crosses(&self, resting: &Order) -> bool {
+        match self.side {
+            Side::Bid => self.price >= resting.price,
+            Side::Ask => self.price <= resting.price,
+        }
+    }
+
+    /// Whether `self` is served before `other` on the same side.
+    fn outranks(&self, other: &Order) -> bool {
+        match self.side {
+            Side::Bid => self.price > other.price,
+            Side::Ask => self.price < other.price,
+        }
+    }
+}
+
+/// A match between a bid and an ask, at the price of the order that was
+/// resting in the book.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub item_id: ItemId,
+    pub price: Money,
+    pub quantity: u64,
+    pub at: DateTime<Utc>,
+}
+
+/// Items and cash the exchange owes a player, waiting to be claimed into
+/// `tile_id`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Settlement {
+    pub player_id: String,
+    pub tile_id: String,
+    pub item_id: ItemId,
+    pub quantity: u64,
+    pub cash: Money,
+}
+
+/// Room for `item_id` in a tile inventory, sent along with a claim so the
+/// exchange only releases what fits.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TileSpace {
+    pub tile_id: String,
+    pub item_id: ItemId,
+    pub fits: u64,
+}
+
+/// Open quantity per price level, best price first, and the latest trades.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Depth {
+    pub bids: Vec<(Money, u64)>,
+    pub asks: Vec<(Money, u64)>,
+    /// Newest first.
+    pub trades: Vec<Trade>,
+}
+
+/// Limit order book for one item.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub item_id: ItemId,
+    /// Highest price first, orders at the same price in arrival order.
+    pub bids: Vec<Order>,
+    /// Lowest price first, orders at the same price in arrival order.
+    pub asks: Vec<Order>,
+    /// Oldest first.
+    pub trades: Vec<Trade>,
+}
+
+/// One match made by `OrderBook::submit`, with both orders as they were
+/// before it.
+struct Fill {
+    bid: Order,
+    ask: Order,
+    price: Money,
+    quantity: u64,
+}
+
+impl OrderBook {
+    pub fn new(item_id: ItemId) -> Self {
+        Self {
+            item_id,
+            bids: Vec::new(),
+            asks: Vec::new(),
+            trades: Vec::new(),
+        }
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut Vec<Order> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    /// Matches `order` against the other side for as long as prices cross,
+    /// then rests whatever is left of it in the book.
+    fn submit(&mut self, mut order: Order) -> Vec<Fill> {
+        let opposite = match order.side {
+            Side::Bid => Side::Ask,
+            Side::Ask => Side::Bid,
+        };
+        let mut fills = Vec::new();
+        let resting = self.side_mut(opposite);
+        while order.remaining > 0 {
+            let Some(best) = resting.first_mut() else {
+                break;
+            };
+            if !order.crosses(best) {
+                break;
+            }
+            let quantity = order.remaining.min(best.remaining);
+            let (bid, ask) = match order.side {
+                Side::Bid => (order.clone(), best.clone()),
+                Side::Ask => (best.clone(), order.clone()),
+            };
+            fills.push(Fill {
+                bid,
+                ask,
+                price: best.price,
+                quantity,
+            });
+            order.remaining -= quantity;
+            best.remaining -= quantity;
+            if best.remaining == 0 {
+                resting.remove(0);
+            }
+        }
+
+        for fill in &fills {
+            self.trades.push(Trade {
+                item_id: self.item_id,
+                price: fill.price,
+                quantity: fill.quantity,
+                at: order.placed_at,
+            });
+        }
+        let excess = self.trades.len().saturating_sub(RECENT_TRADES);
+        self.trades.drain(..excess);
+
+        if order.remaining > 0 {
+            let side = self.side_mut(order.side);
+            let at = side
+                .iter()
+                .position(|resting| order.outranks(resting))
+                .unwrap_or(side.len());
+            side.insert(at, order);
+        }
+        fills
+    }
+
+    pub fn depth(&self) -> Depth {
+        fn levels(orders: &[Order]) -> Vec<(Money, u64)> {
+            let mut levels: Vec<(Money, u64)> = Vec::new();
+            for order in orders {
+                match levels.last_mut() {
+                    Some((price, quantity)) if *price == order.price => {
+                        *quantity += order.remaining
+                    }
+                    _ => levels.push((order.price, order.remaining)),
+                }
+            }
+            levels
+        }
+        Depth {
+            bids: levels(&self.bids),
+            asks: levels(&self.asks),
+            trades: self.trades.iter().rev().cloned().collect(),
+        }
+    }
+}
+
+/// Every order book and what the exchange owes to players. Escrow is held in
+/// the open orders themselves and moves into `settlements` as orders fill or
+/// are cancelled.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub books: Vec<OrderBook>,
+    pub settlements: Vec<Settlement>,
+    next_order_id: u64,
+}
+
+impl Exchange {
+    pub fn book(&self, item_id: ItemId) -> Option<&OrderBook> {
+        self.books.iter().find(|book| book.item_id == item_id)
+    }
+
+    fn book_mut(&mut self, item_id: ItemId) -> &mut OrderBook {
+        match self.books.iter().position(|book| book.item_id == item_id) {
+            Some(i) => &mut self.books[i],
+            None => {
+                self.books.push(OrderBook::new(item_id));
+                self.books.last_mut().unwrap()
+            }
+        }
+    }
+
+    pub fn depth(&self, item_id: ItemId) -> Depth {
+        self.book(item_id).map(OrderBook::depth).unwrap_or_default()
+    }
+
+    fn owe(&mut self, player_id: &str, tile_id: &str, item_id: ItemId, quantity: u64, cash: Money) {
+        if quantity == 0 && cash == Money::ZERO {
+            return;
+        }
+        match self
+            .settlements
+            .iter_mut()
+            .find(|s| s.player_id == player_id && s.tile_id == tile_id && s.item_id == item_id)
+        {
+            Some(settlement) => {
+                settlement.quantity += quantity;
+                settlement.cash += cash;
+            }
+            None => self.settlements.push(Settlement {
+                player_id: player_id.to_string(),
+                tile_id: tile_id.to_string(),
+                item_id,
+                quantity,
+                cash,
+            }),
+        }
+    }
+
+    /// Places an order and matches it right away. Sellers are owed the trade
+    /// price, buyers the items plus the difference to their limit price.
+    pub fn place(
+        &mut self,
+        request: OrderRequest,
+        now: DateTime<Utc>,
+    ) -> Result<(Order, Vec<Trade>), GameError> {
+        if request.quantity == 0 || request.price <= Money::ZERO {
+            return Err(GameError::EmptyOrder);
+        }
+        request
+            .price
+            .checked_mul(request.quantity)
+            .ok_or(GameError::InvalidAmount)?;
+
+        self.next_order_id += 1;
+        let order = Order {
+            id: OrderId(self.next_order_id),
+            player_id: request.player_id,
+            tile_id: request.tile_id,
+            item_id: request.item_id,
+            side: request.side,
+            price: request.price,
+            quantity: request.quantity,
+            remaining: request.quantity,
+            placed_at: now,
+        };
+
+        let fills = self.book_mut(order.item_id).submit(order.clone());
+        let mut trades = Vec::new();
+        for fill in fills {
+            let Fill {
+                bid,
+                ask,
+                price,
+                quantity,
+            } = fill;
+            let refund = (bid.price - price) * quantity;
+            self.owe(&bid.player_id, &bid.tile_id, bid.item_id, quantity, refund);
+            self.owe(
+                &ask.player_id,
+                &ask.tile_id,
+                ask.item_id,
+                0,
+                price * quantity,
+            );
+            trades.push(Trade {
+                item_id: order.item_id,
+                price,
+                quantity,
+                at: now,
+            });
+        }
+        Ok((order, trades))
+    }
+
+    /// Removes an open order of `player_id` and returns its escrow.
+    pub fn cancel(&mut self, player_id: &str, order_id: OrderId) -> Result<Order, GameError> {
+        let order = self
+            .books
+            .iter_mut()
+            .find_map(|book| {
+                [&mut book.bids, &mut book.asks]
+                    .into_iter()
+                    .find_map(|orders| {
+                        let at = orders
+                            .iter()
+                            .position(|o| o.id == order_id && o.player_id == player_id)?;
+                        Some(orders.remove(at))
+                    })
+            })
+            .ok_or(GameError::OrderNotFound)?;
+
+        let item_id = order.item_id;
+        match order.side {
+            Side::Bid => self.owe(
+                player_id,
+                &order.tile_id,
+                item_id,
+                0,
+                order.price * order.remaining,
+            ),
+            Side::Ask => self.owe(
+                player_id,
+                &order.tile_id,
+                item_id,
+                order.remaining,
+                Money::ZERO,
+            ),
+        }
+        Ok(order)
+    }
+
+    pub fn open_orders(&self, player_id: &str) -> Vec<Order> {
+        self.books
+            .iter()
+            .flat_map(|book| book.bids.iter().chain(book.asks.iter()))
+            .filter(|order| order.player_id == player_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Hands over everything owed to `player_id`: all cash, and items up to the
+    /// room given in `space`. Items that do not fit stay owed.
+    pub fn claim(&mut self, player_id: &str, space: &[TileSpace]) -> Vec<Settlement> {
+        let mut claimed = Vec::new();
+        for settlement in self
+            .settlements
+            .iter_mut()
+            .filter(|s| s.player_id == player_id)
+        {
+            let fits = space
+                .iter()
+                .find(|space| {
+                    space.tile_id == settlement.tile_id && space.item_id == settlement.item_id
+                })
+                .map_or(0, |space| space.fits);
+            let quantity = settlement.quantity.min(fits);
+            if quantity == 0 && settlement.cash == Money::ZERO {
+                continue;
+            }
+            claimed.push(Settlement {
+                quantity,
+                cash: settlement.cash,
+                ..settlement.clone()
+            });
+            settlement.quantity -= quantity;
+            settlement.cash = Money::ZERO;
+        }
+        self.settlements
+            .retain(|s| s.quantity > 0 || s.cash != Money::ZERO);
+        claimed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: ItemId = ItemId("LOG");
+
+    fn request(player_id: &str, side: Side, dollars: i64, quantity: u64) -> OrderRequest {
+        OrderRequest {
+            player_id: player_id.to_string(),
+            tile_id: format!("{player_id}-tile"),
+            item_id: LOG,
+            side,
+            price: Money::dollars(dollars),
+            quantity,
+        }
+    }
+
+    fn unlimited(exchange: &Exchange, player_id: &str) -> Vec<TileSpace> {
+        exchange
+            .settlements
+            .iter()
+            .filter(|s| s.player_id == player_id)
+            .map(|s| TileSpace {
+                tile_id: s.tile_id.clone(),
+                item_id: s.item_id,
+                fits: u64::MAX,
+            })
+            .collect()
+    }
+
+    /// Items and cash claimed by `player_id`, with room for everything.
+    fn claim_all(exchange: &mut Exchange, player_id: &str) -> (u64, Money) {
+        let space = unlimited(exchange, player_id);
+        exchange
+            .claim(player_id, &space)
+            .iter()
+            .fold((0, Money::ZERO), |(items, cash), s| {
+                (items + s.quantity, cash + s.cash)
+            })
+    }
+
+    #[test]
+    fn orders_rest_by_price_then_time() {
+        let mut exchange = Exchange::default();
+        let now = DateTime::UNIX_EPOCH;
+        exchange
+            .place(request("alice", Side::Ask, 22, 5), now)
+            .unwrap();
+        exchange
+            .place(request("bob", Side::Ask, 20, 5), now)
+            .unwrap();
+        exchange
+            .place(request("carol", Side::Ask, 20, 3), now)
+            .unwrap();
+        exchange
+            .place(request("dave", Side::Bid, 18, 4), now)
+            .unwrap();
+
+        let book = exchange.book(LOG).unwrap();
+        let sellers: Vec<_> = book.asks.iter().map(|o| o.player_id.as_str()).collect();
+        assert_eq!(sellers, ["bob", "carol", "alice"]);
+        assert_eq!(
+            exchange.depth(LOG).asks,
+            vec![(Money::dollars(20), 8), (Money::dollars(22), 5)]
+        );
+        assert!(exchange.depth(LOG).trades.is_empty());
+    }
+
+    #[test]
+    fn crossing_orders_fill_partially_at_the_resting_price() {
+        let mut exchange = Exchange::default();
+        let now = DateTime::UNIX_EPOCH;
+        exchange
+            .place(request("alice", Side::Ask, 20, 5), now)
+            .unwrap();
+        exchange
+            .place(request("bob", Side::Ask, 21, 5), now)
+            .unwrap();
+        let (order, trades) = exchange
+            .place(request("carol", Side::Bid, 25, 8), now)
+            .unwrap();
+
+        assert_eq!(
+            trades
+                .iter()
+                .map(|t| (t.price, t.quantity))
+                .collect::<Vec<_>>(),
+            vec![(Money::dollars(20), 5), (Money::dollars(21), 3)]
+        );
+        // Bob's ask keeps its last 2 units, Carol's bid was filled completely.
+        assert!(exchange.book(LOG).unwrap().bids.is_empty());
+        assert_eq!(exchange.depth(LOG).asks, vec![(Money::dollars(21), 2)]);
+        assert_eq!(
+            exchange.cancel("carol", order.id),
+            Err(GameError::OrderNotFound)
+        );
+
+        assert_eq!(claim_all(&mut exchange, "alice"), (0, Money::dollars(100)));
+        assert_eq!(claim_all(&mut exchange, "bob"), (0, Money::dollars(63)));
+        // Carol escrowed 8 * $25 and paid 5 * $20 + 3 * $21.
+        assert_eq!(claim_all(&mut exchange, "carol"), (8, Money::dollars(37)));
+        assert!(exchange.settlements.is_empty());
+    }
+
+    #[test]
+    fn cancelling_returns_the_escrow_to_its_owner_only() {
+        let mut exchange = Exchange::default();
+        let now = DateTime::UNIX_EPOCH;
+        let (ask, _) = exchange
+            .place(request("alice", Side::Ask, 20, 5), now)
+            .unwrap();
+        let (bid, _) = exchange
+            .place(request("bob", Side::Bid, 15, 4), now)
+            .unwrap();
+
+        assert_eq!(
+            exchange.cancel("bob", ask.id),
+            Err(GameError::OrderNotFound)
+        );
+        exchange.cancel("alice", ask.id).unwrap();
+        exchange.cancel("bob", bid.id).unwrap();
+
+        assert_eq!(claim_all(&mut exchange, "alice"), (5, Money::ZERO));
+        assert_eq!(claim_all(&mut exchange, "bob"), (0, Money::dollars(60)));
+        assert!(exchange.open_orders("alice").is_empty());
+    }
+
+    #[test]
+    fn claims_only_release_items_that_fit() {
+        let mut exchange = Exchange::default();
+        let now = DateTime::UNIX_EPOCH;
+        exchange
+            .place(request("alice", Side::Ask, 10, 10), now)
+            .unwrap();
+        exchange
+            .place(request("bob", Side::Bid, 10, 10), now)
+            .unwrap();
+
+        let space = [TileSpace {
+            tile_id: "bob-tile".to_string(),
+            item_id: LOG,
+            fits: 4,
+        }];
+        let claimed = exchange.claim("bob", &space);
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].quantity, 4);
+        assert_eq!(exchange.claim("bob", &[]), vec![]);
+        assert_eq!(claim_all(&mut exchange, "bob"), (6, Money::ZERO));
+    }
+
+    #[test]
+    fn refuses_empty_orders() {
+        let mut exchange = Exchange::default();
+        let now = DateTime::UNIX_EPOCH;
+        assert_eq!(
+            exchange.place(request("alice", Side::Bid, 0, 5), now),
+            Err(GameError::EmptyOrder)
+        );
+        assert_eq!(
+            exchange.place(request("alice", Side::Ask, 5, 0), now),
+            Err(GameError::EmptyOrder)
+        );
+    }
+
+    /// Several players trading at random never create or destroy items or
+    /// cash: everything escrowed is either still in an open order or owed back.
+    #[test]
+    fn trading_between_players_conserves_items_and_cash() {
+        let players = ["alice", "bob", "carol", "dave"];
+        let mut exchange = Exchange::default();
+        let (mut items_in, mut cash_in) = (0, Money::ZERO);
+        let mut seed: u64 = 0x2545_f491;
+        let mut next = |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        for round in 0..500 {
+            let player = players[next(4) as usize];
+            if round % 7 == 0 {
+                if let Some(order) = exchange.open_orders(player).first() {
+                    exchange.cancel(player, order.id).unwrap();
+                }
+                continue;
+            }
+            let side = if next(2) == 0 { Side::Bid } else { Side::Ask };
+            let request = request(player, side, 15 + next(10) as i64, 1 + next(20));
+            match side {
+                Side::Bid => cash_in += request.price * request.quantity,
+                Side::Ask => items_in += request.quantity,
+            }
+            exchange.place(request, DateTime::UNIX_EPOCH).unwrap();
+        }
+
+        let (mut items_out, mut cash_out) = (0, Money::ZERO);
+        for order in players.iter().flat_map(|p| exchange.open_orders(p)) {
+            match order.side {
+                Side::Bid => cash_out += order.price * order.remaining,
+                Side::Ask => items_out += order.remaining,
+            }
+        }
+        for player in players {
+            let (items, cash) = claim_all(&mut exchange, player);
+            items_out += items;
+            cash_out += cash;
+        }
+        assert_eq!((items_out, cash_out), (items_in, cash_in));
+        let book = exchange.book(LOG).unwrap();
+        if let (Some(bid), Some(ask)) = (book.bids.first(), book.asks.first()) {
+            assert!(bid.price < ask.price, "the book was left crossed");
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! Player-to-player trading through a limit order book per item, kept on the
+//! server. Players escrow what they offer when placing an order: the items of
+//! an ask leave the tile inventory, the cash of a bid leaves their money. What
+//! the exchange owes them in return becomes a `Settlement`, claimed back into
+//! the game by `PersistGameState`.
+//!
+//! The server takes the escrow from the player's save and refuses orders it
+//! does not cover; the client saves right before placing and takes the same
+//! escrow from its own state. Player ids still come from the `?player=` query,
+//! so the exchange is only meant for trading between players on one machine.
+
+mod book;
+
+pub use book::{
+    Depth, Exchange, Order, OrderBook, OrderId, OrderRequest, Settlement, Side, TileSpace, Trade,
+};
+
+use leptos::prelude::*;
+use leptos::server_fn::codec::Json;
+
+use crate::entities::{
+    GameError, GameSnapshot, GameState, ItemDetails, ItemId, Money, Tile, Transaction,
+};
+use crate::sim::Storage;
+
+/// Takes the escrow of `request` from the player's save, then places it.
+#[server(input = Json)]
+pub async fn place_order(request: OrderRequest) -> Result<(Order, Vec<Trade>), ServerFnError> {
+    use crate::persistence::store;
+
+    // Held while the save is rewritten, so two orders cannot spend the same escrow.
+    let mut exchange = registry::lock().await.map_err(ServerFnError::new)?;
+    let mut save = store::read(&request.player_id)
+        .await
+        .map_err(ServerFnError::new)?
+        .ok_or_else(|| ServerFnError::new(GameError::NoSavedGame))?;
+    save.take_escrow(&request).map_err(ServerFnError::new)?;
+    store::write(&request.player_id, &save)
+        .await
+        .map_err(ServerFnError::new)?;
+    let placed = exchange
+        .place(request, chrono::Utc::now())
+        .map_err(ServerFnError::new)?;
+    registry::save(&exchange).await;
+    Ok(placed)
+}
+
+#[server(input = Json)]
+pub async fn cancel_order(player_id: String, order_id: OrderId) -> Result<Order, ServerFnError> {
+    let mut exchange = registry::lock().await.map_err(ServerFnError::new)?;
+    let order = exchange
+        .cancel(&player_id, order_id)
+        .map_err(ServerFnError::new)?;
+    registry::save(&exchange).await;
+    Ok(order)
+}
+
+#[server(input = Json)]
+pub async fn market_depth(item_id: ItemId) -> Result<Depth, ServerFnError> {
+    let exchange = registry::lock().await.map_err(ServerFnError::new)?;
+    Ok(exchange.depth(item_id))
+}
+
+#[server(input = Json)]
+pub async fn open_orders(player_id: String) -> Result<Vec<Order>, ServerFnError> {
+    let exchange = registry::lock().await.map_err(ServerFnError::new)?;
+    Ok(exchange.open_orders(&player_id))
+}
+
+/// Hands over what the exchange owes `player_id`, see `Exchange::claim`.
+#[server(input = Json)]
+pub async fn claim_settlements(
+    player_id: String,
+    space: Vec<TileSpace>,
+) -> Result<Vec<Settlement>, ServerFnError> {
+    let mut exchange = registry::lock().await.map_err(ServerFnError::new)?;
+    let claimed = exchange.claim(&player_id, &space);
+    if !claimed.is_empty() {
+        registry::save(&exchange).await;
+    }
+    Ok(claimed)
+}
+
+/// The one `Exchange` of the server, written to `EXCHANGE_PATH` after every
+/// change so open orders and their escrow survive a restart.
+#[cfg(feature = "ssr")]
+mod registry {
+    use std::path::Path;
+
+    use tokio::sync::{Mutex, MutexGuard, OnceCell};
+
+    use crate::exchange::Exchange;
+    use crate::persistence::store;
+
+    const EXCHANGE_PATH: &str = "exchange.json";
+
+    static EXCHANGE: OnceCell<Mutex<Exchange>> = OnceCell::const_new();
+
+    /// Locks the exchange, loading it on first use. The lock is held while
+    /// saving so writes land in order.
+    pub async fn lock() -> Result<MutexGuard<'static, Exchange>, String> {
+        let exchange = EXCHANGE
+            .get_or_try_init(|| async {
+                store::read_json(Path::new(EXCHANGE_PATH))
+                    .await
+                    .map(|exchange| Mutex::new(exchange.unwrap_or_default()))
+                    .map_err(|e| format!("Corrupt exchange: {}", e))
+            })
+            .await?;
+        Ok(exchange.lock().await)
+    }
+
+    /// Failing to save does not undo the change: the exchange in memory is
+    /// what players trade against, and the next change saves it again.
+    pub async fn save(exchange: &Exchange) {
+        if let Err(err) = store::write_json(Path::new(EXCHANGE_PATH), exchange).await {
+            leptos::logging::error!("Failed to save the exchange: {}", err);
+        }
+    }
+}
+
+impl OrderRequest {
+    /// Items and cash held by the exchange while the order is open.
+    pub fn escrow(&self) -> Result<(u64, Money), GameError> {
+        if self.quantity == 0 || self.price <= Money::ZERO {
+            return Err(GameError::EmptyOrder);
+        }
+        match self.side {
+            Side::Ask => Ok((self.quantity, Money::ZERO)),
+            Side::Bid => self
+                .price
+                .checked_mul(self.quantity)
+                .map(|cost| (0, cost))
+                .ok_or(GameError::InvalidAmount),
+        }
+    }
+}
+
+impl GameSnapshot {
+    /// Takes the escrow of `request` from the saved cash or the stock of its
+    /// tile, the server side of `Tile::escrow`.
+    pub fn take_escrow(&mut self, request: &OrderRequest) -> Result<(), GameError> {
+        let (items, cash) = request.escrow()?;
+        let tile = self
+            .tiles
+            .iter_mut()
+            .find(|tile| tile.id == request.tile_id && tile.is_owned)
+            .ok_or(GameError::TileNotOwned)?;
+        if cash > self.cash {
+            return Err(GameError::InsufficientFunds {
+                need: cash,
+                have: self.cash,
+            });
+        }
+        let stored = tile.inventory.quantity(request.item_id);
+        if stored < items {
+            return Err(GameError::NotEnoughItems {
+                item_id: request.item_id,
+                requested: items,
+                stored,
+            });
+        }
+        tile.inventory.remove_item(request.item_id, items)?;
+        self.cash -= cash;
+        Ok(())
+    }
+}
+
+impl Settlement {
+    /// Pays out into the player's cash and the inventory of `tile_id`,
+    /// returning how many items did not fit.
+    pub fn apply(&self, game_state: &GameState) -> u64 {
+        if self.cash != Money::ZERO {
+            game_state.cash.update(|money| *money += self.cash);
+        }
+        if self.quantity == 0 {
+            return 0;
+        }
+        let Some(tile) = game_state.tiles.iter().find(|tile| tile.id == self.tile_id) else {
+            return self.quantity;
+        };
+        let added = tile
+            .tile_state
+            .inventory
+            .try_update(|inv| inv.add_item(self.item_id, self.quantity))
+            .unwrap_or(0);
+        self.quantity - added
+    }
+}
+
+impl Tile<'static> {
+    /// Takes the escrow of `request` from the tile inventory or `money`.
+    pub fn escrow(&self, money: RwSignal<Money>, request: &OrderRequest) -> Result<(), GameError> {
+        let (items, cash) = request.escrow()?;
+        if cash > Money::ZERO {
+            return self
+                .tile_state
+                .commit(Transaction::new().pay(money, cash))
+                .map(|_| ());
+        }
+
+        let inventory = self.tile_state.inventory;
+        let stored = inventory.with_untracked(|inv| inv.quantity(request.item_id));
+        if stored < items {
+            return Err(GameError::NotEnoughItems {
+                item_id: request.item_id,
+                requested: items,
+                stored,
+            });
+        }
+        inventory
+            .try_update(|inv| inv.remove_item(request.item_id, items))
+            .unwrap_or(Ok(0))
+            .map(|_| ())
+    }
+
+    /// Gives back the escrow of an order the exchange refused, returning how
+    /// many items no longer fit.
+    pub fn release(&self, money: RwSignal<Money>, request: &OrderRequest) -> u64 {
+        let Ok((items, cash)) = request.escrow() else {
+            return 0;
+        };
+        money.update(|money| *money += cash);
+        if items == 0 {
+            return 0;
+        }
+        let added = self
+            .tile_state
+            .inventory
+            .try_update(|inv| inv.add_item(request.item_id, items))
+            .unwrap_or(0);
+        items - added
+    }
+
+    /// Room for every item in the tile inventory.
+    pub fn free_space(&self) -> Vec<TileSpace> {
+        self.tile_state.inventory.with_untracked(|inv| {
+            ItemDetails::all()
+                .iter()
+                .map(|item| TileSpace {
+                    tile_id: self.id.to_string(),
+                    item_id: item.id,
+                    fits: inv.fits_max_items(item.id),
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::test_owner;
+
+    const LOG: ItemId = ItemId("LOG");
+
+    fn request(tile_id: &str, side: Side, dollars: i64, quantity: u64) -> OrderRequest {
+        OrderRequest {
+            player_id: "alice".to_string(),
+            tile_id: tile_id.to_string(),
+            item_id: LOG,
+            side,
+            price: Money::dollars(dollars),
+            quantity,
+        }
+    }
+
+    /// A save with $100 and one owned tile holding 5 logs.
+    fn save() -> (GameSnapshot, String) {
+        let mut game_state = GameState::new();
+        game_state.cash.set(Money::dollars(100));
+        let tile = Tile::new();
+        tile.is_owned.set(true);
+        tile.tile_state
+            .inventory
+            .update(|inv| assert_eq!(inv.add_item(LOG, 5), 5));
+        game_state.tiles.push(tile);
+        (game_state.snapshot(), tile.id.to_string())
+    }
+
+    #[test]
+    fn bids_take_their_cash_from_the_save() {
+        let _owner = test_owner();
+        let (mut save, tile_id) = save();
+
+        save.take_escrow(&request(&tile_id, Side::Bid, 30, 3))
+            .unwrap();
+        assert_eq!(save.cash, Money::dollars(10));
+        assert_eq!(
+            save.take_escrow(&request(&tile_id, Side::Bid, 20, 1)),
+            Err(GameError::InsufficientFunds {
+                need: Money::dollars(20),
+                have: Money::dollars(10),
+            })
+        );
+        assert_eq!(save.cash, Money::dollars(10));
+    }
+
+    #[test]
+    fn asks_take_their_items_from_an_owned_tile() {
+        let _owner = test_owner();
+        let (mut save, tile_id) = save();
+
+        assert_eq!(
+            save.take_escrow(&request(&tile_id, Side::Ask, 10, 6)),
+            Err(GameError::NotEnoughItems {
+                item_id: LOG,
+                requested: 6,
+                stored: 5,
+            })
+        );
+        save.take_escrow(&request(&tile_id, Side::Ask, 10, 4))
+            .unwrap();
+        assert_eq!(save.tiles[0].inventory.quantity(LOG), 1);
+
+        save.tiles[0].is_owned = false;
+        assert_eq!(
+            save.take_escrow(&request(&tile_id, Side::Ask, 10, 1)),
+            Err(GameError::TileNotOwned)
+        );
+    }
+}
@@ -1,8 +1,12 @@
 pub mod app;
 pub mod components;
 pub mod entities;
+pub mod exchange;
+pub mod persistence;
+pub mod sim;
 pub mod utils;
 pub mod views;
+pub mod world;
 
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
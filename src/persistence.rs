@@ -0,0 +1,90 @@
+use leptos::prelude::*;
+use leptos::server_fn::codec::Json;
+
+use crate::entities::GameSnapshot;
+
+/// Identifies whose save file is read and written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerId(pub String);
+
+impl Default for PlayerId {
+    fn default() -> Self {
+        Self("local".to_string())
+    }
+}
+
+#[server(input = Json)]
+pub async fn save_game(player_id: String, snapshot: GameSnapshot) -> Result<(), ServerFnError> {
+    store::write(&player_id, &snapshot)
+        .await
+        .map_err(ServerFnError::new)
+}
+
+#[server(input = Json)]
+pub async fn load_game(player_id: String) -> Result<Option<GameSnapshot>, ServerFnError> {
+    store::read(&player_id).await.map_err(ServerFnError::new)
+}
+
+/// JSON-on-disk store, one file per player in `SAVE_DIR`.
+#[cfg(feature = "ssr")]
+pub(crate) mod store {
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    use crate::entities::GameSnapshot;
+
+    const SAVE_DIR: &str = "saves";
+
+    fn save_path(player_id: &str) -> Result<PathBuf, String> {
+        let valid = !player_id.is_empty()
+            && player_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("Invalid player id `{}`.", player_id));
+        }
+        Ok(PathBuf::from(SAVE_DIR).join(format!("{player_id}.json")))
+    }
+
+    pub async fn write(player_id: &str, snapshot: &GameSnapshot) -> Result<(), String> {
+        write_json(&save_path(player_id)?, snapshot).await
+    }
+
+    pub async fn read(player_id: &str) -> Result<Option<GameSnapshot>, String> {
+        read_json(&save_path(player_id)?)
+            .await
+            .map_err(|e| format!("Corrupt save for `{}`: {}", player_id, e))
+    }
+
+    pub async fn write_json(path: &Path, value: &impl Serialize) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+
+        // Write to a temporary file first so a crash never leaves a half-written file.
+        let tmp_path = path.with_extension("json.tmp");
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        tokio::fs::write(&tmp_path, json)
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Reads `path`, or `None` if it does not exist yet.
+    pub async fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+        match tokio::fs::read(path).await {
+            Ok(json) => serde_json::from_slice(&json)
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! The game rules on plain data, without signals, so the same code runs in the
+//! browser, on the server and in tests. `GameState::advance` converts the
+//! reactive state into a `SimState`, steps it and writes the result back.
+
+mod payroll;
+mod stock;
+
+pub use payroll::charges_due;
+pub use stock::{Stock, Storage};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::entities::snapshot::{
+    HousingSnapshot, LandSnapshot, TileSnapshot, VehicleSnapshot, WorkersSnapshot,
+};
+use crate::entities::{
+    BuildingId, CatchUp, Charge, GameSnapshot, ItemId, Market, Money, ProductionBuilding,
+    ResourceKind, ResourceMap, SlotMode, VehicleId, VehicleLocation, WorkerType, PAYROLL_PERIOD,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    /// Simulation clock, only moved forward by `step`.
+    pub now: DateTime<Utc>,
+    pub cash: Money,
+    pub last_payroll: DateTime<Utc>,
+    pub tiles: Vec<SimTile>,
+    pub market: Market,
+    pub vehicles: Vec<VehicleSnapshot>,
+}
+
+/// Plain copy of a tile. Counters use the same shapes as the save file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimTile {
+    pub id: String,
+    pub is_owned: bool,
+    pub stock: Stock,
+    pub housing: HousingSnapshot,
+    pub production: Vec<ProductionBuilding>,
+    pub land: LandSnapshot,
+    pub workers: WorkersSnapshot,
+    pub resources: ResourceMap,
+}
+
+/// Something that happened during a `step`, for the caller to report.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimEvent {
+    /// Batches delivered by the buildings of a tile.
+    Produced {
+        tile_id: String,
+        result: CatchUp,
+    },
+    Charged(Charge),
+    /// Unpaid workers who were not assigned to a building quit.
+    WorkersQuit {
+        tile_id: String,
+        worker_type: WorkerType,
+        count: u64,
+    },
+    /// A building was idled because its unpaid workers went on strike.
+    BuildingStruck {
+        tile_id: String,
+        building_id: BuildingId,
+    },
+    /// A vehicle reached its destination and parked there. What did not fit
+    /// in the tile inventory stays on board.
+    Arrived {
+        vehicle_id: VehicleId,
+        tile_id: String,
+        unloaded: Vec<(ItemId, u64)>,
+    },
+    /// Extraction used up the last of a deposit.
+    Depleted {
+        tile_id: String,
+        resource: ResourceKind,
+    },
+}
+
+impl SimState {
+    /// Starts the clock at the moment the snapshot was taken.
+    pub fn from_snapshot(snapshot: &GameSnapshot) -> Self {
+        Self {
+            now: snapshot.saved_at,
+            cash: snapshot.cash,
+            last_payroll: snapshot.last_payroll.unwrap_or(snapshot.saved_at),
+            tiles: snapshot.tiles.iter().map(SimTile::from_snapshot).collect(),
+            market: snapshot.market.clone(),
+            vehicles: snapshot.vehicles.clone(),
+        }
+    }
+
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            cash: self.cash,
+            saved_at: self.now,
+            last_payroll: Some(self.last_payroll),
+            tiles: self.tiles.iter().map(SimTile::snapshot).collect(),
+            market: self.market.clone(),
+            vehicles: self.vehicles.clone(),
+        }
+    }
+}
+
+impl SimTile {
+    pub fn from_snapshot(snapshot: &TileSnapshot) -> Self {
+        Self {
+            id: snapshot.id.clone(),
+            is_owned: snapshot.is_owned,
+            stock: snapshot.inventory.clone(),
+            housing: snapshot.housing,
+            production: snapshot
+                .production
+                .iter()
+                .map(ProductionBuilding::from_snapshot)
+                .collect(),
+            land: snapshot.land,
+            workers: snapshot.workers,
+            resources: snapshot.resources.unwrap_or_default(),
+        }
+    }
+
+    pub fn snapshot(&self) -> TileSnapshot {
+        TileSnapshot {
+            id: self.id.clone(),
+            is_owned: self.is_owned,
+            inventory: self.stock.clone(),
+            housing: self.housing,
+            production: self
+                .production
+                .iter()
+                .map(ProductionBuilding::snapshot)
+                .collect(),
+            land: self.land,
+            workers: self.workers,
+            resources: Some(self.resources),
+        }
+    }
+
+    /// Runs `ProductionBuilding::catch_up` on every building, returning the
+    /// combined result.
+    pub fn catch_up_production(&mut self, now: DateTime<Utc>) -> CatchUp {
+        let mut total = CatchUp::default();
+        for building in self.production.iter_mut() {
+            total.merge(building.catch_up(now, &mut self.stock, &mut self.resources));
+        }
+        total
+    }
+
+    /// Resources whose reserve ran out between `before` and now.
+    fn depleted_since(&self, before: &ResourceMap) -> Vec<ResourceKind> {
+        ResourceKind::all()
+            .into_iter()
+            .filter(|&kind| before.get(kind).reserve > 0 && self.resources.get(kind).reserve == 0)
+            .collect()
+    }
+}
+
+/// Units of `kind` the running extractors among `buildings` take every
+/// `PAYROLL_PERIOD` at their current yield from `resources`.
+pub fn extraction_rate(
+    buildings: &[ProductionBuilding],
+    resources: &ResourceMap,
+    kind: ResourceKind,
+) -> u64 {
+    let period = PAYROLL_PERIOD.num_milliseconds() as u64;
+    buildings
+        .iter()
+        .filter(|b| !b.is_idle() && b.production_type.details().extracts == Some(kind))
+        .flat_map(|b| b.slots.iter().map(|slot| (slot, b.condition)))
+        .filter(|(slot, _)| slot.mode == SlotMode::Running)
+        .filter_map(|(slot, condition)| Some((slot.recipe?, condition)))
+        .map(|(recipe, condition)| {
+            let duration = recipe.duration_at(condition).num_milliseconds().max(1);
+            recipe.batch_output(resources) * period / duration as u64
+        })
+        .sum()
+}
+
+/// Advances the clock by `dt`, catching up production on every tile, unloading
+/// arrived vehicles, charging every payroll period that ended, regrowing
+/// deposits once per period and letting market prices recover. The result
+/// only depends on `state` and `dt`; a negative `dt` is treated as zero.
+pub fn step(state: &mut SimState, dt: Duration) -> Vec<SimEvent> {
+    state.now += dt.max(Duration::zero());
+    let mut events = Vec::new();
+
+    for tile in state.tiles.iter_mut() {
+        let before = tile.resources;
+        let result = tile.catch_up_production(state.now);
+        if result.batches > 0 {
+            events.push(SimEvent::Produced {
+                tile_id: tile.id.clone(),
+                result,
+            });
+        }
+        for resource in tile.depleted_since(&before) {
+            events.push(SimEvent::Depleted {
+                tile_id: tile.id.clone(),
+                resource,
+            });
+        }
+    }
+
+    events.extend(deliver(state));
+
+    while state.last_payroll + PAYROLL_PERIOD <= state.now {
+        state.last_payroll += PAYROLL_PERIOD;
+        for tile in state.tiles.iter_mut() {
+            events.extend(tile.pay_upkeep(state.last_payroll, &mut state.cash));
+            tile.resources.regrow();
+        }
+        events.extend(payroll::pay_fleet(
+            &state.vehicles,
+            state.last_payroll,
+            &mut state.cash,
+        ));
+    }
+
+    state.market.recover(state.now);
+    events
+}
+
+/// Parks every arrived vehicle at its destination and unloads as much cargo
+/// as fits there.
+fn deliver(state: &mut SimState) -> Vec<SimEvent> {
+    let mut events = Vec::new();
+    for vehicle in state.vehicles.iter_mut() {
+        let VehicleLocation::EnRoute(shipment) = &vehicle.location else {
+            continue;
+        };
+        if !shipment.has_arrived(state.now) {
+            continue;
+        }
+        let tile_id = shipment.to.clone();
+        let unloaded = match state.tiles.iter_mut().find(|tile| tile.id == tile_id) {
+            Some(tile) => vehicle.cargo.unload_into(&mut tile.stock),
+            None => Vec::new(),
+        };
+        vehicle.location = VehicleLocation::Parked(tile_id.clone());
+        events.push(SimEvent::Arrived {
+            vehicle_id: vehicle.id.clone(),
+            tile_id,
+            unloaded,
+        });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::snapshot::WorkerCategorySnapshot;
+    use crate::entities::{
+        ChargeKind, GameError, ProductionSlot, ProductionType, Recipe, RecipeId, Shipment,
+        SlotMode, SlotStatus, VehicleType,
+    };
+
+    /// A tile full of water with one running Water Pump, its six workers and
+    /// two spare ones.
+    fn pump_state(cash: Money) -> SimState {
+        let mut pump = ProductionBuilding::new(ProductionType::WaterPump);
+        pump.slots = vec![ProductionSlot::new(
+            Recipe::get(RecipeId("PMP-H2O")).unwrap(),
+        )];
+        let tile = SimTile {
+            id: "T-1".to_string(),
+            is_owned: true,
+            stock: Stock {
+                items: vec![],
+                max_volume: 500_000,
+                max_weight: 500_000,
+            },
+            housing: HousingSnapshot {
+                cheap: 1,
+                standard: 0,
+                fancy: 0,
+            },
+            production: vec![pump],
+            land: LandSnapshot {
+                total: 500,
+                available: 465,
+            },
+            workers: WorkersSnapshot {
+                basic: WorkerCategorySnapshot {
+                    total: 8,
+                    assigned: 6,
+                },
+                advanced: WorkerCategorySnapshot::default(),
+                expert: WorkerCategorySnapshot::default(),
+            },
+            resources: ResourceMap::new(&[(ResourceKind::Water, 100)]),
+        };
+        SimState {
+            now: DateTime::UNIX_EPOCH,
+            cash,
+            last_payroll: DateTime::UNIX_EPOCH,
+            tiles: vec![tile],
+            market: Market::default(),
+            vehicles: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn step_is_deterministic() {
+        let mut a = pump_state(Money::dollars(1));
+        let mut b = a.clone();
+        for dt in [0, 7, 45, 130] {
+            let dt = Duration::seconds(dt);
+            assert_eq!(step(&mut a, dt), step(&mut b, dt));
+        }
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn step_delivers_finished_batches() {
+        let mut state = pump_state(Money::dollars(100));
+        step(&mut state, Duration::zero());
+        let events = step(&mut state, Duration::seconds(50));
+
+        let water = state.tiles[0].stock.quantity(ItemId("H2O"));
+        assert_eq!(water, 15);
+        assert!(events.contains(&SimEvent::Produced {
+            tile_id: "T-1".to_string(),
+            result: CatchUp {
+                batches: 3,
+                produced: vec![(ItemId("H2O"), 15)],
+                consumed: vec![],
+            },
+        }));
+    }
+
+    #[test]
+    fn worn_buildings_work_slower() {
+        let mut state = pump_state(Money::dollars(100));
+        state.tiles[0].production[0].condition = 0.;
+        step(&mut state, Duration::zero());
+        step(&mut state, Duration::seconds(50));
+
+        // Every batch takes twice as long, 30 seconds instead of 15.
+        assert_eq!(state.tiles[0].stock.quantity(ItemId("H2O")), 5);
+        // Restored, the batch due at 60 seconds is followed by two quick ones.
+        state.tiles[0].production[0].upgrade();
+        step(&mut state, Duration::seconds(40));
+        assert_eq!(state.tiles[0].stock.quantity(ItemId("H2O")), 20);
+    }
+
+    #[test]
+    fn extraction_scales_with_abundance() {
+        let mut state = pump_state(Money::dollars(100));
+        state.tiles[0].resources = ResourceMap::new(&[(ResourceKind::Water, 40)]);
+        step(&mut state, Duration::zero());
+        step(&mut state, Duration::seconds(50));
+
+        assert_eq!(state.tiles[0].stock.quantity(ItemId("H2O")), 6);
+    }
+
+    #[test]
+    fn extraction_draws_down_the_reserve() {
+        let mut state = pump_state(Money::dollars(100));
+        step(&mut state, Duration::zero());
+        step(&mut state, Duration::seconds(45));
+        let water = |state: &SimState| state.tiles[0].resources.get(ResourceKind::Water);
+        assert_eq!(water(&state).reserve, 5_000 - 15);
+
+        // A trickle is left at the end, then it regrows with the payroll.
+        state.tiles[0]
+            .resources
+            .get_mut(ResourceKind::Water)
+            .reserve = 2;
+        let events = step(&mut state, Duration::seconds(45));
+        assert_eq!(state.tiles[0].stock.quantity(ItemId("H2O")), 17);
+        assert!(events.contains(&SimEvent::Depleted {
+            tile_id: "T-1".to_string(),
+            resource: ResourceKind::Water,
+        }));
+        assert_eq!(water(&state).reserve, 20);
+    }
+
+    #[test]
+    fn depleted_extractors_deliver_nothing() {
+        let mut state = pump_state(Money::dollars(100));
+        state.tiles[0].production[0].slots[0].batches_left = Some(5);
+        step(&mut state, Duration::zero());
+        state.tiles[0]
+            .resources
+            .get_mut(ResourceKind::Water)
+            .reserve = 0;
+
+        let events = step(&mut state, Duration::seconds(45));
+        assert!(events.is_empty());
+        let slot = &state.tiles[0].production[0].slots[0];
+        assert_eq!(slot.batches_left, Some(5));
+        assert_eq!(
+            slot.status(state.now, &state.tiles[0].stock, &state.tiles[0].resources),
+            SlotStatus::Depleted
+        );
+
+        // The pending batch is delivered once the reserve regrows.
+        step(&mut state, Duration::seconds(15));
+        let events = step(&mut state, Duration::zero());
+        assert!(matches!(events[0], SimEvent::Produced { .. }));
+        assert!(state.tiles[0].production[0].slots[0].batches_left < Some(5));
+    }
+
+    #[test]
+    fn stopping_refunds_inputs_only_when_they_fit() {
+        let mut slot = ProductionSlot::new(Recipe::get(RecipeId("SAW-BRD")).unwrap());
+        let mut stock = Stock {
+            items: vec![(ItemId("LOG"), 1)],
+            max_volume: 500_000,
+            max_weight: 500_000,
+        };
+        let mut resources = ResourceMap::default();
+        slot.catch_up(DateTime::UNIX_EPOCH, 1., &mut stock, &mut resources);
+        assert_eq!(stock.quantity(ItemId("LOG")), 0);
+
+        stock.max_weight = 0;
+        assert_eq!(
+            slot.stop(&mut stock),
+            Err(GameError::InventoryFull {
+                item_id: ItemId("LOG"),
+                requested: 1,
+                fits: 0,
+            })
+        );
+        assert!(slot.next_completion.is_some());
+
+        stock.max_weight = 500_000;
+        assert_eq!(slot.stop(&mut stock), Ok(()));
+        assert_eq!(stock.quantity(ItemId("LOG")), 1);
+        assert_eq!(slot.mode, SlotMode::Stopped);
+    }
+
+    #[test]
+    fn unpaid_workers_walk_out() {
+        let mut state = pump_state(Money::ZERO);
+        let events = step(&mut state, PAYROLL_PERIOD);
+
+        let tile = &state.tiles[0];
+        assert_eq!(tile.workers.basic.total, 6);
+        assert_eq!(tile.workers.basic.assigned, 0);
+        assert!(tile.production[0].is_idle());
+        assert!(events.contains(&SimEvent::WorkersQuit {
+            tile_id: "T-1".to_string(),
+            worker_type: WorkerType::Basic,
+            count: 2,
+        }));
+        assert_eq!(state.last_payroll, state.now);
+    }
+
+    #[test]
+    fn arrived_vehicles_park_and_unload_what_fits() {
+        let mut state = pump_state(Money::dollars(100));
+        state.tiles[0].production.clear();
+        state.tiles[0].stock.max_weight = 12_000;
+        let details = VehicleType::Cart.details();
+        let mut cargo = Stock {
+            items: vec![],
+            max_volume: details.max_volume,
+            max_weight: details.max_weight,
+        };
+        cargo.add_item(ItemId("LOG"), 20);
+        let id = VehicleId::new();
+        state.vehicles.push(VehicleSnapshot {
+            id: id.clone(),
+            vehicle_type: VehicleType::Cart,
+            cargo,
+            location: VehicleLocation::EnRoute(Shipment {
+                from: "T-0".to_string(),
+                to: "T-1".to_string(),
+                departed_at: state.now,
+                arrives_at: state.now + Duration::seconds(30),
+            }),
+        });
+
+        let events = step(&mut state, Duration::seconds(15));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, SimEvent::Arrived { .. })));
+        let events = step(&mut state, Duration::seconds(15));
+        assert!(events.contains(&SimEvent::Arrived {
+            vehicle_id: id,
+            tile_id: "T-1".to_string(),
+            unloaded: vec![(ItemId("LOG"), 12)],
+        }));
+        let vehicle = &state.vehicles[0];
+        assert_eq!(vehicle.location, VehicleLocation::Parked("T-1".to_string()));
+        assert_eq!(vehicle.cargo.quantity(ItemId("LOG")), 8);
+        assert_eq!(state.tiles[0].stock.quantity(ItemId("LOG")), 12);
+    }
+
+    #[test]
+    fn vehicles_are_charged_against_their_tile() {
+        let mut state = pump_state(Money::dollars(100));
+        for _ in 0..2 {
+            state.vehicles.push(VehicleSnapshot {
+                id: VehicleId::new(),
+                vehicle_type: VehicleType::Truck,
+                cargo: Stock::default(),
+                location: VehicleLocation::Parked("T-1".to_string()),
+            });
+        }
+        let events = step(&mut state, PAYROLL_PERIOD);
+
+        assert!(events.contains(&SimEvent::Charged(Charge {
+            at: state.last_payroll,
+            tile_id: "T-1".to_string(),
+            kind: ChargeKind::Vehicle(VehicleType::Truck),
+            count: 2,
+            due: Money::dollars(6),
+            paid: Money::dollars(6),
+        })));
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+
+use crate::entities::snapshot::VehicleSnapshot;
+use crate::entities::{
+    Charge, ChargeKind, HousingType, Money, ProductionType, VehicleType, WorkerType,
+};
+use crate::sim::{SimEvent, SimTile};
+
+/// Takes up to `amount` from `cash`, returning how much was actually paid.
+fn pay(cash: &mut Money, amount: Money) -> Money {
+    let paid = amount.min((*cash).max(Money::ZERO));
+    *cash -= paid;
+    paid
+}
+
+/// Every charge of one payroll period for a tile with the given numbers of
+/// hired workers, housing and buildings, in the order they are paid: wages
+/// first, then maintenance.
+pub fn charges_due(
+    hired: impl Fn(WorkerType) -> u64,
+    housing: impl Fn(HousingType) -> u64,
+    buildings: impl Fn(ProductionType) -> u64,
+) -> Vec<(ChargeKind, u64)> {
+    let wages = WorkerType::all()
+        .into_iter()
+        .map(|worker_type| (ChargeKind::Wages(worker_type), hired(worker_type)));
+    let housing = HousingType::all()
+        .into_iter()
+        .map(|housing_type| (ChargeKind::Housing(housing_type), housing(housing_type)));
+    let buildings = ProductionType::all().into_iter().map(|production_type| {
+        (
+            ChargeKind::Building(production_type),
+            buildings(production_type),
+        )
+    });
+    wages
+        .chain(housing)
+        .chain(buildings)
+        .filter(|&(_, count)| count > 0)
+        .collect()
+}
+
+/// Charges one payroll period of vehicle upkeep, per vehicle type and against
+/// the tile each vehicle is parked at or headed to. Unpaid upkeep has no
+/// further consequence.
+pub(super) fn pay_fleet(
+    vehicles: &[VehicleSnapshot],
+    at: DateTime<Utc>,
+    cash: &mut Money,
+) -> Vec<SimEvent> {
+    let mut fleets: Vec<(&str, VehicleType, u64)> = Vec::new();
+    for vehicle in vehicles {
+        let tile_id = vehicle.location.tile_id();
+        match fleets
+            .iter_mut()
+            .find(|(id, kind, _)| *id == tile_id && *kind == vehicle.vehicle_type)
+        {
+            Some((_, _, count)) => *count += 1,
+            None => fleets.push((tile_id, vehicle.vehicle_type, 1)),
+        }
+    }
+
+    fleets
+        .into_iter()
+        .map(|(tile_id, vehicle_type, count)| {
+            let kind = ChargeKind::Vehicle(vehicle_type);
+            let due = kind.rate() * count;
+            SimEvent::Charged(Charge {
+                at,
+                tile_id: tile_id.to_string(),
+                kind,
+                count,
+                due,
+                paid: pay(cash, due),
+            })
+        })
+        .collect()
+}
+
+impl SimTile {
+    fn owned_buildings(&self, production_type: ProductionType) -> u64 {
+        self.production
+            .iter()
+            .filter(|building| building.production_type == production_type)
+            .count() as u64
+    }
+
+    /// Charges one payroll period against `cash`, see `charges_due`. Workers
+    /// who could not be paid walk out, see `walk_out`.
+    pub(super) fn pay_upkeep(&mut self, at: DateTime<Utc>, cash: &mut Money) -> Vec<SimEvent> {
+        let charges = charges_due(
+            |worker_type| self.workers.category(worker_type).total,
+            |housing_type| self.housing.count(housing_type),
+            |production_type| self.owned_buildings(production_type),
+        );
+
+        let mut events = Vec::new();
+        let mut unpaid = Vec::new();
+        for (kind, count) in charges {
+            let due = kind.rate() * count;
+            let paid = pay(cash, due);
+            events.push(SimEvent::Charged(Charge {
+                at,
+                tile_id: self.id.clone(),
+                kind,
+                count,
+                due,
+                paid,
+            }));
+            if let ChargeKind::Wages(worker_type) = kind {
+                if paid < due {
+                    unpaid.push((worker_type, count - paid.units_of(kind.rate())));
+                }
+            }
+        }
+
+        for (worker_type, count) in unpaid {
+            events.extend(self.walk_out(at, worker_type, count));
+        }
+        events
+    }
+
+    /// Unpaid workers who are not assigned quit. The rest go on strike: the
+    /// buildings they staff are idled until the player reactivates them.
+    fn walk_out(
+        &mut self,
+        at: DateTime<Utc>,
+        worker_type: WorkerType,
+        unpaid: u64,
+    ) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        let category = self.workers.category_mut(worker_type);
+        let quitting = unpaid.min(category.total - category.assigned);
+        category.total -= quitting;
+        if quitting > 0 {
+            events.push(SimEvent::WorkersQuit {
+                tile_id: self.id.clone(),
+                worker_type,
+                count: quitting,
+            });
+        }
+
+        let mut striking = unpaid - quitting;
+        for building in self.production.iter_mut() {
+            if striking == 0 {
+                break;
+            }
+            if building.is_idle() {
+                continue;
+            }
+            let Some(&(_, amount)) = building
+                .workers
+                .iter()
+                .find(|&&(kind, amount)| kind == worker_type && amount > 0)
+            else {
+                continue;
+            };
+
+            building.set_idle(at);
+            for &(kind, released) in &building.workers {
+                let category = self.workers.category_mut(kind);
+                category.assigned = category.assigned.saturating_sub(released);
+            }
+            striking = striking.saturating_sub(amount);
+            events.push(SimEvent::BuildingStruck {
+                tile_id: self.id.clone(),
+                building_id: building.id.clone(),
+            });
+        }
+        events
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{GameError, Inventory, ItemDetails, ItemId};
+
+/// Item storage the production rules run against, implemented by the plain
+/// `Stock` and by the reactive `Inventory`.
+pub trait Storage {
+    /// Quantity of `item_id` currently stored.
+    fn quantity(&self, item_id: ItemId) -> u64;
+    /// How many more of `item_id` fit by both weight and volume.
+    fn fits_max_items(&self, item_id: ItemId) -> u64;
+    /// Stores as many of `quantity` as fit, returning how many were added.
+    fn add_item(&mut self, item_id: ItemId, quantity: u64) -> u64;
+    /// Removes up to `quantity`, returning how many were actually removed.
+    fn remove_item(&mut self, item_id: ItemId, quantity: u64) -> Result<u64, GameError>;
+}
+
+/// Plain counterpart of `Inventory`, weight and volume are always derived from
+/// the stacks.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stock {
+    pub items: Vec<(ItemId, u64)>,
+    pub max_volume: u64,
+    pub max_weight: u64,
+}
+
+impl Stock {
+    pub fn weight(&self) -> u64 {
+        self.total(|details| details.weight)
+    }
+
+    pub fn volume(&self) -> u64 {
+        self.total(|details| details.volume)
+    }
+
+    /// Moves as much as fits into `storage`, returning what was moved.
+    pub fn unload_into(&mut self, storage: &mut impl Storage) -> Vec<(ItemId, u64)> {
+        let mut unloaded = Vec::new();
+        for (item_id, quantity) in self.items.clone() {
+            let moved = storage.add_item(item_id, quantity);
+            if moved > 0 {
+                // Cannot fail, the item is known and stored here.
+                let _ = self.remove_item(item_id, moved);
+                unloaded.push((item_id, moved));
+            }
+        }
+        unloaded
+    }
+
+    fn total(&self, per_item: impl Fn(&ItemDetails) -> u64) -> u64 {
+        self.items
+            .iter()
+            .filter_map(|&(id, quantity)| ItemDetails::get(id).map(|d| per_item(d) * quantity))
+            .sum()
+    }
+}
+
+impl Storage for Stock {
+    fn quantity(&self, item_id: ItemId) -> u64 {
+        self.items
+            .iter()
+            .find(|(id, _)| *id == item_id)
+            .map(|&(_, quantity)| quantity)
+            .unwrap_or(0)
+    }
+
+    fn fits_max_items(&self, item_id: ItemId) -> u64 {
+        let Some(details) = ItemDetails::get(item_id) else {
+            return 0;
+        };
+        u64::min(
+            self.max_volume.saturating_sub(self.volume()) / details.volume,
+            self.max_weight.saturating_sub(self.weight()) / details.weight,
+        )
+    }
+
+    fn add_item(&mut self, item_id: ItemId, quantity: u64) -> u64 {
+        let moved = quantity.min(self.fits_max_items(item_id));
+        if moved == 0 {
+            return 0;
+        }
+        match self.items.iter_mut().find(|(id, _)| *id == item_id) {
+            Some((_, stored)) => *stored += moved,
+            None => self.items.push((item_id, moved)),
+        }
+        moved
+    }
+
+    fn remove_item(&mut self, item_id: ItemId, quantity: u64) -> Result<u64, GameError> {
+        if ItemDetails::get(item_id).is_none() {
+            return Err(GameError::UnknownItem(item_id));
+        }
+        let Some(pos) = self.items.iter().position(|(id, _)| *id == item_id) else {
+            return Ok(0);
+        };
+        let stored = &mut self.items[pos].1;
+        let removed = quantity.min(*stored);
+        *stored -= removed;
+        if *stored == 0 {
+            self.items.remove(pos);
+        }
+        Ok(removed)
+    }
+}
+
+impl Storage for Inventory {
+    fn quantity(&self, item_id: ItemId) -> u64 {
+        Inventory::quantity(self, item_id)
+    }
+
+    fn fits_max_items(&self, item_id: ItemId) -> u64 {
+        Inventory::fits_max_items(self, item_id)
+    }
+
+    fn add_item(&mut self, item_id: ItemId, quantity: u64) -> u64 {
+        Inventory::add_item(self, item_id, quantity)
+    }
+
+    fn remove_item(&mut self, item_id: ItemId, quantity: u64) -> Result<u64, GameError> {
+        Inventory::remove_item(self, item_id, quantity)
+    }
+}
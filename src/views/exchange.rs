@@ -0,0 +1,315 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_use::use_interval_fn;
+
+use crate::components::{use_notifications, Header};
+use crate::entities::{GameState, ItemDetails, ItemId, Money};
+use crate::exchange::{
+    cancel_order, market_depth, open_orders, place_order, Depth, Order, OrderRequest, Side, Trade,
+};
+use crate::persistence::{save_game, PlayerId};
+
+const REFRESH_INTERVAL_MS: u64 = 2_000;
+
+#[component]
+pub fn ExchangePage() -> impl IntoView {
+    let game_state = use_context::<GameState>().expect("GameState context");
+    let player_id = StoredValue::new(use_context::<PlayerId>().expect("PlayerId context").0);
+    let notifications = use_notifications();
+    let money = game_state.cash;
+    let tiles = game_state.tiles.clone();
+    let owned_tiles = Memo::new(move |_| {
+        tiles
+            .iter()
+            .copied()
+            .filter(|tile| tile.is_owned.get())
+            .collect::<Vec<_>>()
+    });
+
+    let item_id = RwSignal::new(ItemId("LOG"));
+    let depth = RwSignal::new(Depth::default());
+    let orders = RwSignal::new(Vec::<Order>::new());
+
+    let refresh = move || {
+        let item = item_id.get_untracked();
+        let player = player_id.get_value();
+        spawn_local(async move {
+            match market_depth(item).await {
+                Ok(book) => depth.set(book),
+                Err(err) => notifications.error(format!("Failed to load the order book: {}", err)),
+            }
+            if let Ok(open) = open_orders(player).await {
+                orders.set(open);
+            }
+        });
+    };
+    Effect::new(move |_| {
+        item_id.track();
+        refresh();
+    });
+    use_interval_fn(refresh, REFRESH_INTERVAL_MS);
+
+    let tile_id = RwSignal::new(None::<&'static str>);
+    let selected_tile = Memo::new(move |_| {
+        let owned = owned_tiles.get();
+        tile_id
+            .get()
+            .and_then(|id| owned.iter().copied().find(|tile| tile.id == id))
+            .or_else(|| owned.first().copied())
+    });
+    let side = RwSignal::new(Side::Bid);
+    let price = RwSignal::new(String::new());
+    let quantity = RwSignal::new(String::from("1"));
+
+    let place = move |_| {
+        let Some(tile) = selected_tile.get_untracked() else {
+            notifications.error("Buy a tile to trade from first.");
+            return;
+        };
+        let Ok(price) = price.get_untracked().parse::<Money>() else {
+            notifications.error("Not a valid price.");
+            return;
+        };
+        let Ok(quantity) = quantity.get_untracked().trim().parse::<u64>() else {
+            notifications.error("Not a valid quantity.");
+            return;
+        };
+        let request = OrderRequest {
+            player_id: player_id.get_value(),
+            tile_id: tile.id.to_string(),
+            item_id: item_id.get_untracked(),
+            side: side.get_untracked(),
+            price,
+            quantity,
+        };
+        // The exchange takes the escrow from the save, so it must see the
+        // state from before the escrow was taken here.
+        let snapshot = game_state.snapshot();
+        if let Err(err) = tile.escrow(money, &request) {
+            notifications.error(err);
+            return;
+        }
+
+        spawn_local(async move {
+            let placed = match save_game(request.player_id.clone(), snapshot).await {
+                Ok(()) => place_order(request.clone()).await,
+                // Not sent, so handled like a refusal.
+                Err(err) => Err(ServerFnError::ServerError(format!(
+                    "the game could not be saved, {}",
+                    err
+                ))),
+            };
+            match placed {
+                Ok((order, trades)) => {
+                    let filled: u64 = trades.iter().map(|trade| trade.quantity).sum();
+                    notifications.success(format!(
+                        "{} {} {} at {}, {} filled.",
+                        order.side.label(),
+                        order.quantity,
+                        item_name(order.item_id),
+                        order.price,
+                        filled
+                    ));
+                }
+                // Refused by the exchange, so it holds nothing and the escrow
+                // goes back.
+                Err(ServerFnError::ServerError(err)) => {
+                    let lost = tile.release(money, &request);
+                    notifications.error(format!("Order refused: {}", err));
+                    if lost > 0 {
+                        notifications.error(format!(
+                            "{} {} did not fit back in {}.",
+                            lost,
+                            item_name(request.item_id),
+                            tile.id
+                        ));
+                    }
+                }
+                // The order may have been placed all the same, keep the escrow
+                // with it rather than paying it out twice.
+                Err(err) => notifications.error(format!(
+                    "Could not reach the exchange: {}. Check your open orders before placing it again.",
+                    err
+                )),
+            }
+            refresh();
+        });
+    };
+
+    let cancel = move |order: Order| {
+        spawn_local(async move {
+            match cancel_order(player_id.get_value(), order.id).await {
+                Ok(order) => notifications.success(format!(
+                    "Cancelled {} {} {}, the escrow is on its way back.",
+                    order.side.label(),
+                    order.remaining,
+                    item_name(order.item_id)
+                )),
+                Err(err) => notifications.error(format!("Failed to cancel: {}", err)),
+            }
+            refresh();
+        });
+    };
+
+    let tab_class = "border px-3 py-1 font-semibold hover:cursor-pointer border-primary-border";
+    let field_class = "bg-secondary-bg border border-primary-border p-1";
+
+    view! {
+        <Header />
+        <div class="flex flex-col gap-6 mx-auto mt-8 px-6 w-full max-w-5xl">
+            <div class="flex items-center gap-2">
+                {ItemDetails::all()
+                    .iter()
+                    .map(|item| {
+                        view! {
+                            <button
+                                on:click=move |_| item_id.set(item.id)
+                                class=tab_class
+                                class=("bg-highlight-dim/30", move || item_id.get() == item.id)
+                            >
+                                {item.name}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+
+            <div class="grid grid-cols-3 gap-6">
+                <div class="flex flex-col gap-1">
+                    <h2 class="text-secondary-text">"ASKS"</h2>
+                    <DepthLevels levels=Signal::derive(move || {
+                        depth.with(|depth| depth.asks.iter().rev().copied().collect())
+                    }) />
+                    <div class="border-t border-primary-border" />
+                    <DepthLevels levels=Signal::derive(move || {
+                        depth.with(|depth| depth.bids.clone())
+                    }) />
+                    <h2 class="text-secondary-text">"BIDS"</h2>
+                </div>
+
+                <div class="flex flex-col gap-1">
+                    <h2 class="text-secondary-text">"RECENT TRADES"</h2>
+                    <For
+                        each=move || depth.get().trades.into_iter().enumerate()
+                        key=|(i, trade)| (*i, trade.at, trade.price, trade.quantity)
+                        children=move |(_, trade): (usize, Trade)| {
+                            view! {
+                                <div class="flex justify-between">
+                                    <span>{trade.price}</span>
+                                    <span>{trade.quantity}</span>
+                                    <span class="text-secondary-text">
+                                        {trade.at.format("%H:%M:%S").to_string()}
+                                    </span>
+                                </div>
+                            }
+                        }
+                    />
+                </div>
+
+                <div class="flex flex-col gap-2">
+                    <h2 class="text-secondary-text">"NEW ORDER"</h2>
+                    <div class="flex gap-2">
+                        <button
+                            on:click=move |_| side.set(Side::Bid)
+                            class=tab_class
+                            class=("bg-highlight-dim/30", move || side.get() == Side::Bid)
+                        >
+                            "BUY"
+                        </button>
+                        <button
+                            on:click=move |_| side.set(Side::Ask)
+                            class=tab_class
+                            class=("bg-destructive-dim/30", move || side.get() == Side::Ask)
+                        >
+                            "SELL"
+                        </button>
+                    </div>
+                    <select
+                        on:change=move |ev| {
+                            let id = event_target_value(&ev);
+                            tile_id.set(owned_tiles.get_untracked().iter().map(|t| t.id).find(|t| *t == id));
+                        }
+                        prop:value=move || selected_tile.get().map(|tile| tile.id).unwrap_or_default()
+                        class=field_class
+                    >
+                        {move || {
+                            owned_tiles
+                                .get()
+                                .into_iter()
+                                .map(|tile| view! { <option value=tile.id>{tile.id}</option> })
+                                .collect_view()
+                        }}
+                    </select>
+                    <label class="flex justify-between items-center gap-2">
+                        "Price"
+                        <input class=field_class placeholder="$0.00" bind:value=price />
+                    </label>
+                    <label class="flex justify-between items-center gap-2">
+                        "Quantity"
+                        <input class=field_class type="number" min="1" bind:value=quantity />
+                    </label>
+                    <button
+                        on:click=place
+                        class="border font-bold py-1 hover:cursor-pointer border-highlight-dim text-highlight-dim hover:bg-highlight-dim/30"
+                    >
+                        "PLACE ORDER"
+                    </button>
+                </div>
+            </div>
+
+            <div class="flex flex-col gap-1">
+                <h2 class="text-secondary-text">"YOUR OPEN ORDERS"</h2>
+                <table class="w-full text-left">
+                    <tbody>
+                        <For
+                            each=move || orders.get()
+                            key=|order| (order.id, order.remaining)
+                            children=move |order: Order| {
+                                view! {
+                                    <tr class="border-t border-primary-border">
+                                        <td class="py-1">{order.side.label()}</td>
+                                        <td>{item_name(order.item_id)}</td>
+                                        <td>{order.price}</td>
+                                        <td>{format!("{} / {}", order.remaining, order.quantity)}</td>
+                                        <td>{order.tile_id.clone()}</td>
+                                        <td class="text-right">
+                                            <button
+                                                on:click=move |_| cancel(order.clone())
+                                                class="border px-2 font-bold hover:cursor-pointer border-destructive-dim hover:bg-destructive-dim/30"
+                                            >
+                                                "CANCEL"
+                                            </button>
+                                        </td>
+                                    </tr>
+                                }
+                            }
+                        />
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+/// Price levels of one side of the book, with the quantity open at each.
+#[component]
+fn DepthLevels(levels: Signal<Vec<(Money, u64)>>) -> impl IntoView {
+    view! {
+        <For
+            each=move || levels.get()
+            key=|level| *level
+            children=move |(price, quantity)| {
+                view! {
+                    <div class="flex justify-between">
+                        <span>{price}</span>
+                        <span>{quantity}</span>
+                    </div>
+                }
+            }
+        />
+    }
+}
+
+fn item_name(item_id: ItemId) -> &'static str {
+    ItemDetails::get(item_id).map_or(item_id.0, |item| item.name)
+}
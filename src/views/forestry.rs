@@ -7,13 +7,13 @@ use crate::{
         button::{Button, ButtonVariant},
         header::Header,
     },
-    entities::GameState,
+    entities::{GameState, Money},
 };
 
 #[component]
 pub fn ForestryPage() -> impl IntoView {
     let game_state = use_context::<GameState>().expect("couldn't get context");
-    let log_price = 20.;
+    let log_price = 20;
 
     let fell_progress = RwSignal::new(0);
 
@@ -21,18 +21,18 @@ pub fn ForestryPage() -> impl IntoView {
         *fell_progress.write() += 1;
         if fell_progress.get() >= 10 {
             fell_progress.set(0);
-            *game_state.cash.write() += 1.0;
+            *game_state.cash.write() += Money::dollars(1);
         }
     };
     let rent_forwarder = move || {
-        if game_state.cash.get() >= 250. {
-            *game_state.cash.write() -= 250.;
-            if game_state.cash.get() >= 25.0 {
-                *game_state.cash.write() -= 25.0;
-                *game_state.cash.write() += 25. * log_price;
+        if game_state.cash.get() >= Money::dollars(250) {
+            *game_state.cash.write() -= Money::dollars(250);
+            if game_state.cash.get() >= Money::dollars(25) {
+                *game_state.cash.write() -= Money::dollars(25);
+                *game_state.cash.write() += Money::dollars(25) * log_price;
             } else {
                 *game_state.cash.write() += game_state.cash.get() * log_price;
-                game_state.cash.set(0.0);
+                game_state.cash.set(Money::ZERO);
             }
         }
     };
@@ -68,7 +68,7 @@ pub fn ForestryPage() -> impl IntoView {
     view! {
         <Header />
         <div class="flex flex-col items-center justify-center mt-11 mx-auto gap-4 max-w-3xl px-6 sm:px-6 lg:px-8">
-            <h4>"Logs (1x = $20): "{move || game_state.cash.get()}</h4>
+            <h4>"Logs (1x = $20): "{move || game_state.cash.get().to_string()}</h4>
             <Button variant=ButtonVariant::Green on_click=inc_logs>
                 <div>"Fell tree " {fell_progress} "/10"</div>
             </Button>
@@ -78,7 +78,7 @@ pub fn ForestryPage() -> impl IntoView {
                 <Button
                     variant=ButtonVariant::Red
                     on_click=rent_forwarder
-                    disabled=Signal::derive(move || game_state.cash.get() < 250.)
+                    disabled=Signal::derive(move || game_state.cash.get() < Money::dollars(250))
                 >
                     "Pay $250"
                 </Button>
@@ -90,7 +90,7 @@ pub fn ForestryPage() -> impl IntoView {
                 <Button
                     variant=ButtonVariant::Red
                     on_click=hire_harvester_opr
-                    disabled=Signal::derive(move || game_state.cash.get() < 30.)
+                    disabled=Signal::derive(move || game_state.cash.get() < Money::dollars(30))
                 >
                     "Hire"
                 </Button>
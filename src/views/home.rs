@@ -7,6 +7,9 @@ pub fn HomePage() -> impl IntoView {
             <h1 class="flex flex-1 m-auto">"Welcome to Trade World!"</h1>
             <div class="flex flex-1 flex-col">
                 <a href="/tile-map">"Tile map"</a>
+                <a href="/market">"Market"</a>
+                <a href="/exchange">"Exchange"</a>
+                <a href="/shipments">"Fleet"</a>
                 <a class="text-gray-400" href="/forestry">
                     "Forestry"
                 </a>
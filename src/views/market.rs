@@ -0,0 +1,171 @@
+use leptos::prelude::*;
+
+use crate::components::{use_notifications, Header};
+use crate::entities::{GameState, ItemDetails, MarketItem, Tile};
+
+/// Quantities offered as buy and sell buttons.
+const TRADE_AMOUNTS: [u64; 3] = [1, 10, 100];
+
+#[component]
+pub fn MarketPage() -> impl IntoView {
+    let game_state = use_context::<GameState>().expect("GameState context");
+    let tiles = game_state.tiles.clone();
+    let owned_tiles = Memo::new(move |_| {
+        tiles
+            .iter()
+            .copied()
+            .filter(|tile| tile.is_owned.get())
+            .collect::<Vec<_>>()
+    });
+    let selected = RwSignal::new(None::<&'static str>);
+    let selected_tile = Memo::new(move |_| {
+        let owned = owned_tiles.get();
+        selected
+            .get()
+            .and_then(|id| owned.iter().copied().find(|tile| tile.id == id))
+            .or_else(|| owned.first().copied())
+    });
+
+    view! {
+        <Header />
+        <div class="flex flex-col gap-6 mx-auto mt-8 px-6 w-full max-w-5xl">
+            <div class="flex items-center gap-4">
+                <span class="text-secondary-text">"TRADE FROM"</span>
+                {move || {
+                    owned_tiles
+                        .get()
+                        .into_iter()
+                        .map(|tile| {
+                            let is_selected = move || {
+                                selected_tile.get().is_some_and(|s| s.id == tile.id)
+                            };
+                            view! {
+                                <button
+                                    on:click=move |_| selected.set(Some(tile.id))
+                                    class="border px-3 py-1 font-semibold hover:cursor-pointer border-primary-border"
+                                    class=("bg-highlight-dim/30", is_selected)
+                                >
+                                    {tile.id}
+                                </button>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </div>
+            <Show
+                when=move || selected_tile.get().is_some()
+                fallback=|| {
+                    view! { <div class="text-secondary-text">"Buy a tile to start trading."</div> }
+                }
+            >
+                <table class="w-full text-left">
+                    <thead class="text-secondary-text text-sm">
+                        <tr>
+                            <th class="py-2">"ITEM"</th>
+                            <th>"SELL AT"</th>
+                            <th>"BUY AT"</th>
+                            <th>"VS BASE"</th>
+                            <th>"STORED"</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {MarketItem::all()
+                            .iter()
+                            .map(|item| {
+                                view! { <MarketRow item selected_tile=selected_tile.into() /> }
+                            })
+                            .collect_view()}
+                    </tbody>
+                </table>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+fn MarketRow(
+    item: &'static MarketItem,
+    selected_tile: Signal<Option<Tile<'static>>>,
+) -> impl IntoView {
+    let game_state = use_context::<GameState>().expect("GameState context");
+    let notifications = use_notifications();
+    let (market, money) = (game_state.market, game_state.cash);
+    let item_id = item.item_id;
+    let name = ItemDetails::get(item_id).map_or(item_id.0, |details| details.name);
+
+    let stored = move || {
+        selected_tile
+            .get()
+            .map(|tile| tile.tile_state.inventory.with(|inv| inv.quantity(item_id)))
+            .unwrap_or(0)
+    };
+    let change = move || {
+        let price = market
+            .with(|market| market.price(item_id))
+            .unwrap_or_default();
+        let change = (price.cents() - item.base_price.cents()) * 100 / item.base_price.cents();
+        format!("{:+}%", change)
+    };
+
+    let sell = move |quantity: u64| {
+        let Some(tile) = selected_tile.get_untracked() else {
+            return;
+        };
+        notifications.report(
+            tile.sell_items(market, money, item_id, quantity),
+            |(sold, proceeds)| format!("Sold {} {} for {}.", sold, name, proceeds),
+        );
+    };
+    let buy = move |quantity: u64| {
+        let Some(tile) = selected_tile.get_untracked() else {
+            return;
+        };
+        notifications.report(
+            tile.buy_items(market, money, item_id, quantity),
+            |(bought, cost)| format!("Bought {} {} for {}.", bought, name, cost),
+        );
+    };
+
+    view! {
+        <tr class="border-t border-primary-border">
+            <td class="py-2 font-semibold">{name}</td>
+            <td class="text-highlight-dim">
+                {move || market.with(|market| market.bid(item_id)).unwrap_or_default().to_string()}
+            </td>
+            <td class="text-destructive">
+                {move || market.with(|market| market.price(item_id)).unwrap_or_default().to_string()}
+            </td>
+            <td class="text-secondary-text">{change}</td>
+            <td>{stored}</td>
+            <td class="flex gap-1 justify-end py-2">
+                {TRADE_AMOUNTS
+                    .into_iter()
+                    .map(|quantity| {
+                        view! {
+                            <button
+                                on:click=move |_| sell(quantity)
+                                class="border px-2 font-bold hover:cursor-pointer border-highlight-dim text-highlight-dim hover:bg-highlight-dim/30"
+                            >
+                                {format!("SELL {}", quantity)}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+                {TRADE_AMOUNTS
+                    .into_iter()
+                    .map(|quantity| {
+                        view! {
+                            <button
+                                on:click=move |_| buy(quantity)
+                                class="border px-2 font-bold hover:cursor-pointer border-destructive-dim hover:bg-destructive-dim/30"
+                            >
+                                {format!("BUY {}", quantity)}
+                            </button>
+                        }
+                    })
+                    .collect_view()}
+            </td>
+        </tr>
+    }
+}
@@ -1,9 +1,15 @@
+pub mod exchange;
 pub mod forestry;
 pub mod home;
+pub mod market;
+pub mod shipments;
 pub mod tile;
 pub mod tile_map;
 
+pub use exchange::ExchangePage;
 pub use forestry::ForestryPage;
 pub use home::HomePage;
+pub use market::MarketPage;
+pub use shipments::ShipmentsPage;
 pub use tile::TilePage;
 pub use tile_map::TileMapPage;
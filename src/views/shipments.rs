@@ -0,0 +1,394 @@
+use chrono::{DateTime, Utc};
+use leptos::prelude::*;
+use leptos_use::use_interval_fn;
+
+use crate::components::{away_summary::format_stacks, use_notifications, Header};
+use crate::entities::{
+    GameState, Inventory, ItemDetails, ItemId, Tile, Vehicle, VehicleLocation, VehicleType,
+};
+use crate::sim::Storage;
+
+#[component]
+pub fn ShipmentsPage() -> impl IntoView {
+    let game_state = use_context::<GameState>().expect("GameState context");
+    let notifications = use_notifications();
+    let tiles = game_state.tiles.clone();
+    let owned_tiles = Memo::new(move |_| {
+        tiles
+            .iter()
+            .copied()
+            .filter(|tile| tile.is_owned.get())
+            .collect::<Vec<_>>()
+    });
+    let find_tile = move |id: &str| owned_tiles.get().into_iter().find(|tile| tile.id == id);
+    let vehicles = game_state.vehicles;
+
+    let field_class = "bg-secondary-bg border border-primary-border p-1";
+    let tile_options = move || {
+        owned_tiles
+            .get()
+            .into_iter()
+            .map(|tile: Tile| view! { <option value=tile.id>{tile.id}</option> })
+            .collect_view()
+    };
+
+    let depot = RwSignal::new(String::new());
+    let vehicle_type = RwSignal::new(VehicleType::Cart);
+    let buy = {
+        let game_state = game_state.clone();
+        move |_| {
+            let Some(tile) = find_tile(&depot.get_untracked()) else {
+                notifications.error("Pick a tile to park the vehicle at.");
+                return;
+            };
+            let result = game_state.buy_vehicle(&tile, vehicle_type.get_untracked());
+            notifications.report(result, |vehicle| {
+                format!("{} is parked at {}.", vehicle.name(), tile.id)
+            });
+        }
+    };
+
+    let vehicle_id = RwSignal::new(String::new());
+    let to = RwSignal::new(String::new());
+    let item_id = RwSignal::new(String::new());
+    let quantity = RwSignal::new(String::from("1"));
+    let cargo = RwSignal::new(Vec::<(ItemId, u64)>::new());
+
+    let parked = move || {
+        vehicles
+            .get()
+            .into_iter()
+            .filter(|vehicle| {
+                vehicle
+                    .location
+                    .with(|location| location.parked_at().is_some())
+            })
+            .collect::<Vec<_>>()
+    };
+    let selected = Memo::new(move |_| {
+        vehicles
+            .get()
+            .into_iter()
+            .find(|vehicle| vehicle.id.0 == vehicle_id.get())
+    });
+    let source = Memo::new(move |_| {
+        selected.get().and_then(|vehicle| {
+            vehicle
+                .location
+                .with(|location| location.parked_at().and_then(find_tile))
+        })
+    });
+    let destination = Memo::new(move |_| find_tile(&to.get()));
+    let stored_items = move || {
+        source
+            .get()
+            .map(|tile| {
+                tile.tile_state.inventory.with(|inv| {
+                    inv.items
+                        .get()
+                        .iter()
+                        .map(|stack| stack.id)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .unwrap_or_default()
+    };
+    let load = move || {
+        let Some(vehicle) = selected.get() else {
+            return String::new();
+        };
+        let mut hold = vehicle.inventory.with(Inventory::snapshot);
+        for &(item_id, quantity) in cargo.get().iter() {
+            hold.add_item(item_id, quantity);
+        }
+        format!(
+            "Load: {} / {} weight, {} / {} volume",
+            hold.weight(),
+            hold.max_weight,
+            hold.volume(),
+            hold.max_volume
+        )
+    };
+    let travel_time = {
+        let game_state = game_state.clone();
+        move || match (selected.get(), source.get(), destination.get()) {
+            (Some(vehicle), Some(a), Some(b)) => match game_state.route(&a, &b) {
+                Some(route) => format!(
+                    "{} hexes, {}s on the road",
+                    route.hexes.len() - 1,
+                    vehicle.vehicle_type.travel_time(route.cost).num_seconds()
+                ),
+                None => "No route leads there.".to_string(),
+            },
+            _ => "Pick a parked vehicle and a destination.".to_string(),
+        }
+    };
+
+    let add_cargo = move |_| {
+        let Some(item) = ItemDetails::all()
+            .iter()
+            .find(|item| item.id.0 == item_id.get_untracked())
+        else {
+            notifications.error("Pick an item to load.");
+            return;
+        };
+        let Ok(amount) = quantity.get_untracked().trim().parse::<u64>() else {
+            notifications.error("Not a valid quantity.");
+            return;
+        };
+        cargo.update(
+            |cargo| match cargo.iter_mut().find(|(id, _)| *id == item.id) {
+                Some((_, loaded)) => *loaded += amount,
+                None => cargo.push((item.id, amount)),
+            },
+        );
+    };
+
+    let dispatch = {
+        let game_state = game_state.clone();
+        move |_| {
+            let (Some(vehicle), Some(b)) = (selected.get_untracked(), destination.get_untracked())
+            else {
+                notifications.error("Pick a parked vehicle and a destination.");
+                return;
+            };
+            let result = game_state.dispatch(&vehicle.id, &b, &cargo.get_untracked(), Utc::now());
+            if result.is_ok() {
+                cargo.set(Vec::new());
+                vehicle_id.set(String::new());
+            }
+            notifications.report(result, |shipment| {
+                format!(
+                    "{} left {} for {}.",
+                    vehicle.name(),
+                    shipment.from,
+                    shipment.to
+                )
+            });
+        }
+    };
+
+    let now = RwSignal::new(Utc::now());
+    use_interval_fn(move || now.set(Utc::now()), 1000);
+
+    view! {
+        <Header />
+        <div class="flex flex-col gap-6 mx-auto mt-8 px-6 w-full max-w-5xl">
+            <div class="flex flex-col gap-2">
+                <h2 class="text-secondary-text">"BUY VEHICLE"</h2>
+                <div class="flex gap-4 items-center">
+                    <select
+                        on:change=move |ev| depot.set(event_target_value(&ev))
+                        prop:value=move || depot.get()
+                        class=field_class
+                    >
+                        <option value="">"Park at..."</option>
+                        {tile_options}
+                    </select>
+                    <select
+                        on:change=move |ev| {
+                            let name = event_target_value(&ev);
+                            if let Some(kind) = VehicleType::all()
+                                .into_iter()
+                                .find(|kind| kind.details().name == name)
+                            {
+                                vehicle_type.set(kind);
+                            }
+                        }
+                        prop:value=move || vehicle_type.get().details().name
+                        class=field_class
+                    >
+                        {VehicleType::all()
+                            .into_iter()
+                            .map(|kind| {
+                                let name = kind.details().name;
+                                view! { <option value=name>{name}</option> }
+                            })
+                            .collect_view()}
+                    </select>
+                    <button
+                        on:click=buy
+                        class="border px-3 py-1 font-bold hover:cursor-pointer border-highlight-dim text-highlight-dim hover:bg-highlight-dim/30"
+                    >
+                        "BUY"
+                    </button>
+                </div>
+                <div class="text-secondary-text">
+                    {move || {
+                        let details = vehicle_type.get().details();
+                        format!(
+                            "{} {} Costs {}, upkeep {} per period. Carries {} weight, {} volume, {}s per hex.",
+                            details.name,
+                            details.description,
+                            details.cost,
+                            details.upkeep,
+                            details.max_weight,
+                            details.max_volume,
+                            details.time_per_hex.num_seconds(),
+                        )
+                    }}
+                </div>
+            </div>
+
+            <div class="flex flex-col gap-2">
+                <h2 class="text-secondary-text">"NEW SHIPMENT"</h2>
+                <div class="flex gap-4 items-center">
+                    <label class="flex gap-2 items-center">
+                        "Vehicle"
+                        <select
+                            on:change=move |ev| {
+                                vehicle_id.set(event_target_value(&ev));
+                                cargo.set(Vec::new());
+                            }
+                            prop:value=move || vehicle_id.get()
+                            class=field_class
+                        >
+                            <option value="">"-"</option>
+                            {move || {
+                                parked()
+                                    .into_iter()
+                                    .map(|vehicle| {
+                                        let label = format!(
+                                            "{} at {}",
+                                            vehicle.name(),
+                                            vehicle.location.with(|location| location.tile_id().to_string()),
+                                        );
+                                        view! { <option value=vehicle.id.0>{label}</option> }
+                                    })
+                                    .collect_view()
+                            }}
+                        </select>
+                    </label>
+                    <label class="flex gap-2 items-center">
+                        "To"
+                        <select
+                            on:change=move |ev| to.set(event_target_value(&ev))
+                            prop:value=move || to.get()
+                            class=field_class
+                        >
+                            <option value="">"-"</option>
+                            {tile_options}
+                        </select>
+                    </label>
+                </div>
+                <div class="flex gap-4 items-center">
+                    <select
+                        on:change=move |ev| item_id.set(event_target_value(&ev))
+                        prop:value=move || item_id.get()
+                        class=field_class
+                    >
+                        <option value="">"Select item"</option>
+                        {move || {
+                            stored_items()
+                                .into_iter()
+                                .map(|id| {
+                                    let name = ItemDetails::get(id).map_or(id.0, |item| item.name);
+                                    view! { <option value=id.0>{name}</option> }
+                                })
+                                .collect_view()
+                        }}
+                    </select>
+                    <input class=field_class type="number" min="1" bind:value=quantity />
+                    <button
+                        on:click=add_cargo
+                        class="border px-3 py-1 font-bold hover:cursor-pointer border-primary-border hover:bg-highlight-dim/30"
+                    >
+                        "LOAD"
+                    </button>
+                    <button
+                        on:click=move |_| cargo.set(Vec::new())
+                        class="border px-3 py-1 font-bold hover:cursor-pointer border-destructive-dim hover:bg-destructive-dim/30"
+                    >
+                        "CLEAR"
+                    </button>
+                </div>
+                <div>"Cargo: "{move || format_stacks(&cargo.get(), 'x')}</div>
+                <div class="text-secondary-text">{load}</div>
+                <div class="text-secondary-text">{travel_time}</div>
+                <button
+                    on:click=dispatch
+                    class="self-start border px-3 py-1 font-bold hover:cursor-pointer border-highlight-dim text-highlight-dim hover:bg-highlight-dim/30"
+                >
+                    "DISPATCH"
+                </button>
+            </div>
+
+            <div class="flex flex-col gap-2">
+                <h2 class="text-secondary-text">"FLEET"</h2>
+                <Show
+                    when=move || vehicles.with(|vehicles| !vehicles.is_empty())
+                    fallback=|| view! { <div class="text-secondary-text">"No vehicles."</div> }
+                >
+                    <For
+                        each=move || vehicles.get()
+                        key=|vehicle| vehicle.id.clone()
+                        children=move |vehicle: Vehicle| {
+                            view! { <VehicleRow vehicle now /> }
+                        }
+                    />
+                </Show>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn VehicleRow(vehicle: Vehicle, now: RwSignal<DateTime<Utc>>) -> impl IntoView {
+    let location = vehicle.location;
+    let status = move || match location.get() {
+        VehicleLocation::Parked(tile_id) => format!("Parked at {}", tile_id),
+        VehicleLocation::EnRoute(shipment) => {
+            let now = now.get();
+            if shipment.has_arrived(now) {
+                format!("{} -> {}, unloading", shipment.from, shipment.to)
+            } else {
+                format!(
+                    "{} -> {}, {}s left",
+                    shipment.from,
+                    shipment.to,
+                    (shipment.arrives_at - now).num_seconds()
+                )
+            }
+        }
+    };
+    let progress = move || match location.get() {
+        VehicleLocation::Parked(_) => None,
+        VehicleLocation::EnRoute(shipment) => Some(shipment.progress(now.get())),
+    };
+    let cargo = move || {
+        let cargo: Vec<_> = vehicle.inventory.with(|inv| {
+            inv.items
+                .get()
+                .iter()
+                .map(|stack| (stack.id, stack.quantity.get()))
+                .collect()
+        });
+        if cargo.is_empty() {
+            "Empty".to_string()
+        } else {
+            format_stacks(&cargo, 'x')
+        }
+    };
+
+    view! {
+        <div class="flex flex-col gap-1 border-t border-primary-border pt-2">
+            <div class="flex justify-between">
+                <span class="font-semibold">{vehicle.name()}</span>
+                <span class="text-secondary-text">{status}</span>
+            </div>
+            <div>{cargo}</div>
+            {move || {
+                progress()
+                    .map(|progress| {
+                        view! {
+                            <progress
+                                class="w-full h-3 bg-primary-bg border border-primary-text [&::-webkit-progress-value]:bg-highlight [&::-moz-progress-bar]:bg-highlight"
+                                max=1000
+                                value=(progress * 1000.) as u64
+                            />
+                        }
+                    })
+            }}
+        </div>
+    }
+}
@@ -1,17 +1,28 @@
+use chrono::{DateTime, Utc};
 use leptos::ev::{mousemove, mouseup, MouseEvent};
 use leptos::prelude::*;
 use leptos::Params;
 use leptos_router::hooks::use_params;
 use leptos_router::params::Params;
+use leptos_use::use_interval_fn;
 
+use crate::components::{away_summary::format_elapsed, Tabs, TabsContent, TabsList, TabsTrigger};
+use crate::components::{
+    use_notifications, DragState, DraggableItemOverlay, Header, InventoryContainer,
+};
 use crate::components::{
     Accordion, AccordionContent, AccordionItem, AccordionTrigger, AccordionType,
 };
-use crate::components::{DragState, DraggableItemOverlay, Header, InventoryContainer};
-use crate::components::{Tabs, TabsContent, TabsList, TabsTrigger};
-use crate::entities::{GameState, HousingType, ProductionType, Tile, WorkerType};
+use crate::entities::{
+    BuildingId, Forecast, GameError, GameState, HousingType, Inventory, Money, ProductionSlot,
+    ProductionType, Recipe, SlotMode, SlotStatus, Tile, Vehicle, WorkerType, PAYROLL_PERIOD,
+};
+use crate::sim::charges_due;
+
+/// Ledger lines shown in the overview.
+const RECENT_CHARGES: usize = 8;
 
-fn use_money() -> RwSignal<f64> {
+fn use_money() -> RwSignal<Money> {
     use_context::<GameState>()
         .expect("GameState context not found.")
         .cash
@@ -52,14 +63,29 @@ pub fn TilePage() -> impl IntoView {
         }
     });
 
+    let notifications = use_notifications();
     window_event_listener(mouseup, move |_e: MouseEvent| {
         if let Some(drag_info) = drag_state.dragging.get() {
-            if let Some(destination) = drag_info.destination {
-                drag_info
-                    .source
-                    .update(|inv| inv.remove_item(drag_info.item_id, drag_info.to_transfer));
-
-                destination.update(|inv| inv.add_item(drag_info.item_id, drag_info.to_transfer));
+            if let Some(destination) = drag_info.destination.filter(|&d| d != drag_info.source) {
+                let (item_id, requested) = (drag_info.item_id, drag_info.to_transfer);
+                let moved = drag_info.source.try_update(|source| {
+                    destination
+                        .try_update(|destination| {
+                            source.transfer_to(destination, item_id, requested)
+                        })
+                        .unwrap_or(Ok(0))
+                });
+                match moved {
+                    Some(Ok(moved)) if moved < requested => {
+                        notifications.error(GameError::InventoryFull {
+                            item_id,
+                            requested,
+                            fits: moved,
+                        })
+                    }
+                    Some(Err(err)) => notifications.error(err),
+                    _ => {}
+                }
             }
         }
         drag_state.dragging.set(None);
@@ -87,6 +113,9 @@ pub fn TilePage() -> impl IntoView {
                         <TabsContent value="workers">
                             <WorkersTab />
                         </TabsContent>
+                        <TabsContent value="production">
+                            <ProductionTab />
+                        </TabsContent>
                     </Tabs>
                 </div>
                 <div class="flex flex-1">
@@ -97,6 +126,9 @@ pub fn TilePage() -> impl IntoView {
                                 view! { <InventoryContainer inventory=inventory /> }
                             }}
                         </div>
+                        <div class="flex flex-col max-h-[50%] overflow-y-auto">
+                            <ParkedVehicles />
+                        </div>
                     </div>
                 </div>
             </div>
@@ -104,15 +136,248 @@ pub fn TilePage() -> impl IntoView {
     }
 }
 
+/// Vehicles parked at the tile. Items are loaded and unloaded by dragging them
+/// between the vehicle and the tile inventory.
+#[component]
+fn ParkedVehicles() -> impl IntoView {
+    let vehicles = use_context::<GameState>()
+        .expect("GameState context")
+        .vehicles;
+    let tile = use_tile();
+    let parked = move || {
+        vehicles
+            .get()
+            .into_iter()
+            .filter(|vehicle| {
+                vehicle
+                    .location
+                    .with(|location| location.parked_at() == Some(tile.id))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    view! {
+        <For
+            each=parked
+            key=|vehicle| vehicle.id.clone()
+            children=move |vehicle: Vehicle| view! { <ParkedVehicle vehicle /> }
+        />
+    }
+}
+
+#[component]
+fn ParkedVehicle(vehicle: Vehicle) -> impl IntoView {
+    let game_state = use_context::<GameState>().expect("GameState context");
+    let notifications = use_notifications();
+    let tile = use_tile();
+    let destination = RwSignal::new(String::new());
+    let tiles = game_state.tiles.clone();
+    let destinations = move || {
+        tiles
+            .iter()
+            .copied()
+            .filter(|other| other.id != tile.id && other.is_owned.get())
+            .map(|other| view! { <option value=other.id>{other.id}</option> })
+            .collect_view()
+    };
+
+    let send = {
+        let vehicle = vehicle.clone();
+        move |_| {
+            let Some(to) = game_state
+                .tiles
+                .iter()
+                .find(|other| other.id == destination.get_untracked())
+            else {
+                notifications.error("Pick a destination.");
+                return;
+            };
+            let result = game_state.dispatch(&vehicle.id, to, &[], Utc::now());
+            notifications.report(result, |shipment| {
+                format!(
+                    "{} left for {}, arriving in {}s.",
+                    vehicle.name(),
+                    shipment.to,
+                    (shipment.arrives_at - shipment.departed_at).num_seconds()
+                )
+            });
+        }
+    };
+
+    view! {
+        <div class="flex flex-col h-64 shrink-0 border-b border-primary-border">
+            <InventoryContainer inventory=vehicle.inventory />
+            <div class="flex gap-2 items-center px-2 pb-2">
+                <select
+                    on:change=move |ev| destination.set(event_target_value(&ev))
+                    prop:value=move || destination.get()
+                    class="bg-secondary-bg border border-primary-border p-1"
+                >
+                    <option value="">"Send to..."</option>
+                    {destinations}
+                </select>
+                <button
+                    on:click=send
+                    class="border px-3 py-1 font-bold hover:cursor-pointer border-highlight-dim text-highlight-dim hover:bg-highlight-dim/30"
+                >
+                    "SEND"
+                </button>
+            </div>
+        </div>
+    }
+}
+
 #[component]
 pub fn OverviewTab() -> impl IntoView {
     let tile = use_tile();
+    let ledger = use_context::<GameState>()
+        .expect("GameState context not found.")
+        .ledger;
+
+    let slot_statuses = move || {
+        let now = Utc::now();
+        let resources = tile.tile_state.resources.get();
+        let inventory = tile.tile_state.inventory;
+        tile.tile_state
+            .buildings
+            .production
+            .buildings
+            .with(|buildings| {
+                buildings
+                    .iter()
+                    .flat_map(|building| {
+                        let name = building.production_type.details().name;
+                        let resources = &resources;
+                        building.slots.iter().map(move |slot| {
+                            let output = slot.recipe.map(|recipe| recipe.item_id.0);
+                            let status = inventory.with(|inv| slot.status(now, inv, resources));
+                            (name, output, status)
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+    };
+
+    let upkeep = move || -> Money {
+        charges_due(
+            |worker_type| tile.hired_workers(worker_type),
+            |housing_type| tile.owned_housing(housing_type),
+            |production_type| tile.owned_production_buildings(production_type),
+        )
+        .into_iter()
+        .map(|(kind, count)| kind.rate() * count)
+        .sum()
+    };
+
+    let forecasts = move || {
+        tile.tile_state
+            .resources
+            .get()
+            .present()
+            .into_iter()
+            .map(|(kind, deposit)| (kind, deposit, tile.depletion_forecast(kind)))
+            .collect::<Vec<_>>()
+    };
+
+    let recent_charges = move || {
+        ledger.with(|ledger| {
+            ledger
+                .iter()
+                .rev()
+                .filter(|charge| charge.tile_id == tile.id)
+                .take(RECENT_CHARGES)
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
 
     view! {
         <div class="flex flex-col">
             <ul>
                 <li>{move || { tile.tile_state.buildings.housing.cheap.get() }}</li>
             </ul>
+            <ul class="pt-2">
+                {move || {
+                    slot_statuses()
+                        .into_iter()
+                        .map(|(name, output, status)| {
+                            view! {
+                                <li class="flex justify-between">
+                                    <span>{name}</span>
+                                    <span class="font-semibold">{output.unwrap_or("-")}</span>
+                                    <span
+                                        class="text-secondary-text"
+                                        class=(
+                                            "text-destructive",
+                                            status != SlotStatus::Running,
+                                        )
+                                    >
+                                        {status.label()}
+                                    </span>
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+            <div class="pt-4 font-semibold">"Resources"</div>
+            <ul class="pt-2">
+                {move || {
+                    forecasts()
+                        .into_iter()
+                        .map(|(kind, deposit, forecast)| {
+                            let (label, warn) = match forecast {
+                                Forecast::Untouched => ("Untouched".to_string(), false),
+                                Forecast::Sustained => ("Sustained".to_string(), false),
+                                Forecast::DepletedIn(_) if deposit.reserve == 0 => {
+                                    ("Depleted".to_string(), true)
+                                }
+                                Forecast::DepletedIn(left) => {
+                                    (format!("Depleted in {}", format_elapsed(left)), true)
+                                }
+                            };
+                            view! {
+                                <li class="flex justify-between">
+                                    <span>{kind.details().name}</span>
+                                    <span class="text-secondary-text">
+                                        {format!("{} / {}", deposit.reserve, deposit.capacity)}
+                                    </span>
+                                    <span class=("text-destructive", warn)>{label}</span>
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+            <div class="flex justify-between pt-4 font-semibold">
+                <span>"Upkeep"</span>
+                <span class="text-destructive">
+                    {move || format!("{} / {}m", upkeep(), PAYROLL_PERIOD.num_minutes())}
+                </span>
+            </div>
+            <ul class="pt-2 text-sm">
+                {move || {
+                    recent_charges()
+                        .into_iter()
+                        .map(|charge| {
+                            view! {
+                                <li class="flex justify-between">
+                                    <span>
+                                        {format!("{} x{}", charge.kind.label(), charge.count)}
+                                    </span>
+                                    <span class="text-secondary-text">
+                                        {charge.at.format("%H:%M").to_string()}
+                                    </span>
+                                    <span class=(
+                                        "text-destructive",
+                                        !charge.is_paid_in_full(),
+                                    )>{format!("-{}", charge.paid)}</span>
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
         </div>
     }
 }
@@ -121,6 +386,7 @@ pub fn OverviewTab() -> impl IntoView {
 pub fn BuildingsTab() -> impl IntoView {
     let tile = use_tile();
     let money = use_money();
+    let notifications = use_notifications();
 
     view! {
         <Accordion of_type=AccordionType::Multiple collapsible=true>
@@ -148,15 +414,15 @@ pub fn BuildingsTab() -> impl IntoView {
                                     </div>
                                     <div class="space-x-6">
                                         <span class="font-semibold text-lg text-highlight-dim">
-                                            {move || format!("${:.2}", details.cost)}
+                                            {details.cost}
                                         </span>
                                         <button
                                             on:click=move |_| {
-                                                if let Err(err) = tile
-                                                    .build_production(production_type, money, 1)
-                                                {
-                                                    leptos::logging::log!("{}", err);
-                                                }
+                                                notifications
+                                                    .report(
+                                                        tile.build_production(production_type, money, 1),
+                                                        |_| format!("Built {}.", details.name),
+                                                    );
                                             }
                                             class="border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer my-1 py-2 px-4"
                                         >
@@ -164,6 +430,48 @@ pub fn BuildingsTab() -> impl IntoView {
                                         </button>
                                     </div>
                                 </div>
+                                <For
+                                    each=move || {
+                                        tile.tile_state
+                                            .buildings
+                                            .production
+                                            .buildings
+                                            .with(|buildings| {
+                                                buildings
+                                                    .iter()
+                                                    .filter(|b| b.production_type == production_type)
+                                                    .map(|b| b.id.clone())
+                                                    .collect::<Vec<_>>()
+                                            })
+                                    }
+                                    key=|id| id.clone()
+                                    children=move |building_id: BuildingId| {
+                                        let label = format!("#{}", building_id.short());
+                                        view! {
+                                            <div class="w-full flex justify-between items-center pl-4 pb-2 text-sm">
+                                                <span class="text-secondary-text">{label}</span>
+                                                <button
+                                                    on:click=move |_| {
+                                                        notifications
+                                                            .report(
+                                                                tile.destroy_production(&building_id, money),
+                                                                |salvage| {
+                                                                    format!(
+                                                                        "Demolished {}, salvaged {}.",
+                                                                        details.name,
+                                                                        salvage,
+                                                                    )
+                                                                },
+                                                            );
+                                                    }
+                                                    class="border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer py-1 px-3"
+                                                >
+                                                    "DEMOLISH"
+                                                </button>
+                                            </div>
+                                        }
+                                    }
+                                />
                             }
                         }
                     />
@@ -193,19 +501,39 @@ pub fn BuildingsTab() -> impl IntoView {
                                     </div>
                                     <div class="space-x-6">
                                         <span class="font-semibold text-lg text-highlight-dim">
-                                            {move || format!("${:.2}", details.cost)}
+                                            {details.cost}
                                         </span>
                                         <button
                                             on:click=move |_| {
-                                                if let Err(err) = tile.build_housing(housing_type, money, 1)
-                                                {
-                                                    leptos::logging::log!("{}",err);
-                                                }
+                                                notifications
+                                                    .report(
+                                                        tile.build_housing(housing_type, money, 1),
+                                                        |_| format!("Built {}.", details.name),
+                                                    );
                                             }
                                             class="border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer my-1 py-2 px-4"
                                         >
                                             "BUILD"
                                         </button>
+                                        <button
+                                            on:click=move |_| {
+                                                notifications
+                                                    .report(
+                                                        tile.destroy_housing(housing_type, 1),
+                                                        |evicted| {
+                                                            format!(
+                                                                "Demolished {}, evicted {} {}s.",
+                                                                details.name,
+                                                                evicted,
+                                                                housing_type.worker_type().details().name,
+                                                            )
+                                                        },
+                                                    );
+                                            }
+                                            class="border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer my-1 py-2 px-4"
+                                        >
+                                            "DEMOLISH"
+                                        </button>
                                     </div>
                                 </div>
                             }
@@ -221,6 +549,7 @@ pub fn BuildingsTab() -> impl IntoView {
 #[component]
 pub fn WorkersTab() -> impl IntoView {
     let money = use_money();
+    let notifications = use_notifications();
     let tile = use_tile();
     // let error_message = RwSignal::new(None);
 
@@ -241,27 +570,43 @@ pub fn WorkersTab() -> impl IntoView {
                                         <div class="text-sm">
                                             {move || {
                                                 format!(
-                                                    "Capacity {}/{}",
+                                                    "Capacity {}/{} | Assigned {}",
                                                     tile.hired_workers(worker_type),
                                                     tile.workers_can_accommodate(worker_type),
+                                                    tile.hired_workers(worker_type)
+                                                        - tile.tile_state.workers.get_available(worker_type),
                                                 )
                                             }}
                                         </div>
                                     </div>
                                     <div class="space-x-6">
                                         <span class="font-semibold text-lg text-highlight-dim">
-                                            {move || format!("${:.2}", details.cost)}
+                                            {details.cost}
                                         </span>
                                         <button
                                             on:click=move |_| {
-                                                if let Err(err) = tile.hire_workers(worker_type, money, 1) {
-                                                    leptos::logging::log!("{}",err);
-                                                }
+                                                notifications
+                                                    .report(
+                                                        tile.hire_workers(worker_type, money, 1),
+                                                        |_| format!("Hired a {}.", details.name),
+                                                    );
                                             }
                                             class="border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer my-1 py-2 px-4"
                                         >
                                             "HIRE"
                                         </button>
+                                        <button
+                                            on:click=move |_| {
+                                                notifications
+                                                    .report(
+                                                        tile.fire_workers(worker_type, 1),
+                                                        |_| format!("Fired a {}.", details.name),
+                                                    );
+                                            }
+                                            class="border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer my-1 py-2 px-4"
+                                        >
+                                            "FIRE"
+                                        </button>
                                     </div>
                                 </div>
                             }
@@ -273,11 +618,360 @@ pub fn WorkersTab() -> impl IntoView {
     }
 }
 
-// #[component]
-// pub fn ProductionTab() -> impl IntoView {
-//     let tile = use_tile();
+#[component]
+pub fn ProductionTab() -> impl IntoView {
+    let tile = use_tile();
+    let now = RwSignal::new(Utc::now());
+    use_interval_fn(move || now.set(Utc::now()), 250);
+
+    let building_ids = move || {
+        tile.tile_state
+            .buildings
+            .production
+            .buildings
+            .with(|buildings| {
+                buildings
+                    .iter()
+                    .map(|building| building.id.clone())
+                    .collect::<Vec<_>>()
+            })
+    };
 
-//     view! {
-//         <For each=tile.get
-//     }
-// }
+    view! {
+        <div class="flex flex-col gap-2 px-2">
+            <Show when=move || building_ids().is_empty()>
+                <div class="text-secondary-text">"No production buildings on this tile."</div>
+            </Show>
+            <For
+                each=building_ids
+                key=|building_id| building_id.clone()
+                children=move |building_id| view! { <ProductionBuildingCard building_id now /> }
+            />
+        </div>
+    }
+}
+
+#[component]
+fn ProductionBuildingCard(building_id: BuildingId, now: RwSignal<DateTime<Utc>>) -> impl IntoView {
+    let tile = use_tile();
+    let money = use_money();
+    let notifications = use_notifications();
+    let building_id = StoredValue::new(building_id);
+
+    let building = move || {
+        building_id.with_value(|id| {
+            tile.tile_state
+                .buildings
+                .production
+                .buildings
+                .with(|buildings| {
+                    buildings
+                        .iter()
+                        .find(|building| &building.id == id)
+                        .cloned()
+                })
+        })
+    };
+
+    let Some(initial) = building() else {
+        return ().into_any();
+    };
+    let details = initial.production_type.details();
+    let production_type = initial.production_type;
+
+    let slot_indices = move || building().map(|b| 0..b.slots.len()).unwrap_or(0..0);
+    let is_idle = move || building().is_some_and(|b| b.is_idle());
+    let summary = move || {
+        building()
+            .map(|b| {
+                format!(
+                    "Level {} | Condition {:.0}% | Upgrade {}",
+                    b.level,
+                    b.condition * 100.,
+                    b.upgrade_cost(),
+                )
+            })
+            .unwrap_or_default()
+    };
+
+    let toggle_idle = move |_| {
+        let idle = !is_idle();
+        let result = building_id.with_value(|id| tile.set_production_idle(id, idle));
+        notifications.report(result, |_| {
+            let state = if idle { "idled" } else { "reactivated" };
+            format!("{} {}.", details.name, state)
+        });
+    };
+    let upgrade = move |_| {
+        let result = building_id.with_value(|id| tile.upgrade_production(id, money));
+        notifications.report(result, |_| format!("{} upgraded.", details.name));
+    };
+
+    let demolish = move |_| {
+        let result = building_id.with_value(|id| tile.destroy_production(id, money));
+        notifications.report(result, |salvage| {
+            format!("Demolished {}, salvaged {}.", details.name, salvage)
+        });
+    };
+
+    let button_class = "border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer py-1 px-3";
+
+    view! {
+        <div class="flex flex-col gap-1 pb-2 border-b border-primary-border">
+            <div class="flex justify-between items-center">
+                <div class="flex flex-col">
+                    <div class="text-md font-semibold">
+                        {details.name}
+                        <span class="pl-2 text-sm text-secondary-text">
+                            {building_id.with_value(|id| format!("#{}", id.short()))}
+                        </span>
+                    </div>
+                    <div class="text-sm text-secondary-text">{summary}</div>
+                </div>
+                <div class="flex items-center gap-2">
+                    <button on:click=toggle_idle class=button_class>
+                        {move || if is_idle() { "ACTIVATE" } else { "IDLE" }}
+                    </button>
+                    <button on:click=upgrade class=button_class>
+                        "UPGRADE"
+                    </button>
+                    <button on:click=demolish class=button_class>
+                        "DEMOLISH"
+                    </button>
+                </div>
+            </div>
+            <For
+                each=slot_indices
+                key=|index| *index
+                children=move |index| {
+                    view! { <ProductionSlotCard building_id production_type index now /> }
+                }
+            />
+        </div>
+    }
+    .into_any()
+}
+
+#[component]
+fn ProductionSlotCard(
+    building_id: StoredValue<BuildingId>,
+    production_type: ProductionType,
+    index: usize,
+    now: RwSignal<DateTime<Utc>>,
+) -> impl IntoView {
+    let tile = use_tile();
+    let notifications = use_notifications();
+
+    let slot = move || {
+        building_id.with_value(|id| {
+            tile.tile_state
+                .buildings
+                .production
+                .buildings
+                .with(|buildings| {
+                    buildings
+                        .iter()
+                        .find(|building| &building.id == id)
+                        .and_then(|building| building.slots.get(index).cloned())
+                })
+        })
+    };
+    let update_slot = move |f: &dyn Fn(&mut ProductionSlot, &mut Inventory)| {
+        building_id.with_value(|id| {
+            tile.tile_state
+                .update_slot(id, index, |slot, inv| f(slot, inv))
+        });
+    };
+    let recipes = Recipe::for_building(production_type);
+
+    let status = move || {
+        let resources = tile.tile_state.resources.get();
+        slot().map(|slot| {
+            tile.tile_state
+                .inventory
+                .with(|inv| slot.status(now.get(), inv, &resources))
+        })
+    };
+    let progress = move || {
+        slot()
+            .and_then(|slot| slot.progress(now.get()))
+            .unwrap_or(0.)
+    };
+    let queued = move || match slot().and_then(|slot| slot.batches_left) {
+        Some(batches) => batches.to_string(),
+        None => "∞".to_string(),
+    };
+    let selected_recipe = move || {
+        slot()
+            .and_then(|slot| slot.recipe)
+            .map(|recipe| recipe.id.0)
+            .unwrap_or_default()
+    };
+
+    let on_select = move |ev| {
+        let value = event_target_value(&ev);
+        let recipe = Recipe::all().iter().find(|recipe| recipe.id.0 == value);
+        let result = building_id.with_value(|id| {
+            tile.tile_state.update_building(id, |building, inv| {
+                building.select_recipe(index, recipe, inv)
+            })
+        });
+        if let Some(Err(err)) = result {
+            notifications.error(err);
+        }
+    };
+    let start = move |_| {
+        building_id.with_value(|id| {
+            tile.tile_state.update_building(id, |building, inv| {
+                let condition = building.condition;
+                if let Some(slot) = building.slots.get_mut(index) {
+                    tile.tile_state.resources.update(|resources| {
+                        slot.start(Utc::now(), condition, inv, resources);
+                    });
+                }
+            })
+        });
+    };
+    let stop = move |_| {
+        let result = building_id.with_value(|id| {
+            tile.tile_state
+                .update_slot(id, index, |slot, inv| slot.stop(inv))
+        });
+        if let Some(Err(err)) = result {
+            notifications.error(err);
+        }
+    };
+
+    let button_class = "border font-bold hover:bg-destructive-dim/30 border-destructive-dim hover:cursor-pointer py-1 px-3";
+
+    view! {
+        <div class="flex flex-col gap-1 pl-2">
+            <div class="flex justify-between items-baseline">
+                <div class="text-sm font-semibold">{format!("Slot {}", index + 1)}</div>
+                <span
+                    class="text-sm"
+                    class=(
+                        "text-destructive",
+                        move || {
+                            matches!(
+                                status(),
+                                Some(
+                                    SlotStatus::WaitingForInputs
+                                    | SlotStatus::StorageFull
+                                    | SlotStatus::Depleted,
+                                )
+                            )
+                        },
+                    )
+                >
+                    {move || status().map(|status| status.label())}
+                </span>
+            </div>
+            <select
+                on:change=on_select
+                prop:value=selected_recipe
+                class="bg-secondary-bg border border-primary-border p-1"
+            >
+                <option value="">"Select recipe"</option>
+                {recipes
+                    .into_iter()
+                    .map(|recipe| {
+                        let inputs = recipe
+                            .inputs
+                            .iter()
+                            .map(|(id, quantity)| format!("{}x {}", quantity, id.0))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        view! {
+                            <option value=recipe.id.0>
+                                {format!(
+                                    "{}: {} -> {}x {} ({}s)",
+                                    recipe.name,
+                                    if inputs.is_empty() { "-".to_string() } else { inputs },
+                                    tile
+                                        .tile_state
+                                        .resources
+                                        .with_untracked(|resources| recipe.batch_output(resources)),
+                                    recipe.item_id.0,
+                                    recipe.batch_duration.num_seconds(),
+                                )}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+            <progress
+                class="w-full h-3 bg-primary-bg border border-primary-text [&::-webkit-progress-value]:bg-highlight [&::-moz-progress-bar]:bg-highlight"
+                max=1000
+                value=move || (progress() * 1000.) as u64
+            />
+            <div class="flex justify-between items-center">
+                <div class="flex items-center gap-2">
+                    <span>"Queued: "{queued}</span>
+                    <button
+                        on:click=move |_| update_slot(&|slot, _| slot.queue_batches(1))
+                        class=button_class
+                    >
+                        "+1"
+                    </button>
+                    <button
+                        on:click=move |_| update_slot(&|slot, _| slot.queue_batches(10))
+                        class=button_class
+                    >
+                        "+10"
+                    </button>
+                    <button
+                        on:click=move |_| update_slot(&|slot, _| slot.repeat_forever())
+                        class=button_class
+                    >
+                        "∞"
+                    </button>
+                </div>
+                <div class="flex items-center gap-2">
+                    {move || match slot().map(|slot| slot.mode) {
+                        Some(SlotMode::Running) => {
+                            view! {
+                                <button
+                                    on:click=move |_| update_slot(&|slot, _| slot.pause(Utc::now()))
+                                    class=button_class
+                                >
+                                    "PAUSE"
+                                </button>
+                            }
+                                .into_any()
+                        }
+                        Some(SlotMode::Paused(_)) => {
+                            view! {
+                                <button
+                                    on:click=move |_| update_slot(&|slot, _| slot.resume(Utc::now()))
+                                    class=button_class
+                                >
+                                    "RESUME"
+                                </button>
+                            }
+                                .into_any()
+                        }
+                        _ => {
+                            view! {
+                                <button
+                                    on:click=start
+                                    class=button_class
+                                >
+                                    "START"
+                                </button>
+                            }
+                                .into_any()
+                        }
+                    }}
+                    <button
+                        on:click=stop
+                        class=button_class
+                    >
+                        "STOP"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+        .into_any()
+}
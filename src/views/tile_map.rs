@@ -1,5 +1,17 @@
-use crate::{components::header::Header, entities::GameState};
-use leptos::{ev::MouseEvent, prelude::*};
+use std::collections::HashMap;
+
+use crate::{
+    components::header::Header,
+    entities::{GameState, ResourceMap, Terrain},
+    world::{camera::Camera, hex::Hex},
+};
+use leptos::{
+    ev::{keydown, resize, KeyboardEvent, MouseEvent, PointerEvent, WheelEvent},
+    prelude::*,
+    svg::Svg,
+    wasm_bindgen::JsCast,
+    web_sys,
+};
 use leptos_router::components::A;
 
 #[component]
@@ -64,11 +76,12 @@ fn TileOverview() -> impl IntoView {
             </div>
 
             <div class="flex-1 flex p-4 space-y-4 overflow-y-auto border-b border-primary-border">
-                <div class="text-2xl">
+                <div class="text-2xl w-full">
+                    <p class="">"Terrain: "{move || tile_info.get().terrain.details().name}</p>
                     <p class="">"Description: "{move || tile_info.get().description}</p>
-                    <p class="">
-                        "Resources: "{move || format!("{:?}", tile_info.get().resources)}
-                    </p>
+                    <ResourceBars resources=Signal::derive(move || {
+                        tile_info.get().tile_state.resources.get()
+                    }) />
                 </div>
             </div>
 
@@ -88,7 +101,7 @@ fn TileOverview() -> impl IntoView {
                     }
                 >
                     <div class="text-3xl font-semibold">
-                        "Price: $"{move || format!("{:.2}", tile_info.get().price)}
+                        "Price: "{move || tile_info.get().price.to_string()}
                     </div>
                     <button
                         on:click=buy_tile
@@ -102,21 +115,169 @@ fn TileOverview() -> impl IntoView {
     }
 }
 
+/// Abundance of every resource present, shrinking as the reserve is extracted.
+#[component]
+fn ResourceBars(resources: Signal<ResourceMap>) -> impl IntoView {
+    view! {
+        <div class="flex flex-col gap-2 pt-4">
+            {move || {
+                resources
+                    .get()
+                    .present()
+                    .into_iter()
+                    .map(|(kind, deposit)| {
+                        let details = kind.details();
+                        view! {
+                            <div class="flex flex-col gap-1">
+                                <div class="flex justify-between text-xl">
+                                    <span>{details.name}</span>
+                                    <span class="text-secondary-text">
+                                        {format!(
+                                            "{}% ({} / {} units)",
+                                            deposit.abundance,
+                                            deposit.reserve,
+                                            deposit.capacity,
+                                        )}
+                                    </span>
+                                </div>
+                                <div class="w-full h-3 bg-primary-bg border border-primary-border">
+                                    <div
+                                        class=format!("h-full {}", details.color)
+                                        style:width=format!(
+                                            "{}%",
+                                            deposit.abundance as u64 * deposit.reserve
+                                                / deposit.capacity.max(1),
+                                        )
+                                    />
+                                </div>
+                            </div>
+                        }
+                    })
+                    .collect_view()
+            }}
+        </div>
+    }
+}
+
+/// Circumradius of a hex at zoom 1, in pixels.
+const TILE_SIZE: f64 = 50.;
+/// Zoom change per wheel notch or key press.
+const ZOOM_STEP: f64 = 1.15;
+/// Pixels panned per arrow or WASD key press.
+const KEY_PAN: f64 = 80.;
+/// Size of the map view until it is measured, also used when rendering on the
+/// server.
+const DEFAULT_VIEWPORT: (f64, f64) = (1200., 800.);
+
 #[component]
 fn Grid() -> impl IntoView {
-    let tile_size = 50.0;
+    let tile_size = TILE_SIZE;
+    let selected_tile =
+        use_context::<SelectedTile>().expect("failed to get SelectedTile from context");
 
-    let pan_offset = RwSignal::new((0.0, 0.0));
+    let svg_ref = NodeRef::<Svg>::new();
+    // Shifted so the first hex touches the corner of the view.
+    let camera = RwSignal::new(Camera {
+        pan: (tile_size + 1., (3.0_f64).sqrt() * tile_size / 2. + 1.),
+        ..Camera::default()
+    });
+    let viewport = RwSignal::new(DEFAULT_VIEWPORT);
     let is_dragging = RwSignal::new(false);
     let drag_start = RwSignal::new((0.0, 0.0));
     let pan_start = RwSignal::new((0.0, 0.0));
+    // Fingers on the map, by pointer id, for panning and pinch zoom.
+    let touches = RwSignal::new(Vec::<(i32, (f64, f64))>::new());
+
+    let measure = move || {
+        if let Some(svg) = svg_ref.get_untracked() {
+            viewport.set((svg.client_width() as f64, svg.client_height() as f64));
+        }
+    };
+    Effect::new(move |_| {
+        if svg_ref.get().is_some() {
+            measure();
+        }
+    });
+    let on_resize = window_event_listener(resize, move |_| measure());
+    on_cleanup(move || on_resize.remove());
+
+    // Position of a pointer relative to the map view.
+    let to_view = move |client_x: i32, client_y: i32| {
+        let (x, y) = (client_x as f64, client_y as f64);
+        match svg_ref.get_untracked() {
+            Some(svg) => {
+                let rect = svg.get_bounding_client_rect();
+                (x - rect.left(), y - rect.top())
+            }
+            None => (x, y),
+        }
+    };
+
+    let game_state = use_context::<GameState>().expect("cannot get GameState from context");
+    let tiles = game_state.tiles;
+    let by_offset: HashMap<_, _> = tiles
+        .iter()
+        .map(|tile| ((tile.row, tile.col), *tile))
+        .collect();
+
+    let center_on_selected = {
+        let by_offset = by_offset.clone();
+        move || {
+            let id = selected_tile.0.get_untracked();
+            if let Some(tile) = by_offset.values().find(|tile| tile.id == id) {
+                let center = tile.hex().to_pixel(tile_size);
+                camera.update(|camera| camera.center_on(center, viewport.get_untracked()));
+            }
+        }
+    };
+
+    let on_keydown = window_event_listener(keydown, {
+        let center_on_selected = center_on_selected.clone();
+        move |e: KeyboardEvent| {
+            let typing = e
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                .is_some_and(|el| {
+                    matches!(el.tag_name().as_str(), "INPUT" | "SELECT" | "TEXTAREA")
+                });
+            if typing || e.ctrl_key() || e.meta_key() || e.alt_key() {
+                return;
+            }
+            let (width, height) = viewport.get_untracked();
+            let middle = (width / 2., height / 2.);
+            match e.key().as_str() {
+                "ArrowUp" | "w" | "W" => camera.update(|c| c.pan_by(0., KEY_PAN)),
+                "ArrowDown" | "s" | "S" => camera.update(|c| c.pan_by(0., -KEY_PAN)),
+                "ArrowLeft" | "a" | "A" => camera.update(|c| c.pan_by(KEY_PAN, 0.)),
+                "ArrowRight" | "d" | "D" => camera.update(|c| c.pan_by(-KEY_PAN, 0.)),
+                "+" | "=" => camera.update(|c| c.zoom_at(middle, ZOOM_STEP)),
+                "-" => camera.update(|c| c.zoom_at(middle, 1. / ZOOM_STEP)),
+                "c" | "C" => center_on_selected(),
+                _ => return,
+            }
+            e.prevent_default();
+        }
+    });
+    on_cleanup(move || on_keydown.remove());
+
+    let (rows, cols) = tiles.iter().fold((0, 0), |(rows, cols), tile| {
+        (rows.max(tile.row + 1), cols.max(tile.col + 1))
+    });
+    let visible = Memo::new(move |_| {
+        let (visible_rows, visible_cols) = camera.get().visible(viewport.get(), tile_size);
+        let visible_cols = visible_cols.start..visible_cols.end.min(cols);
+        (visible_rows.start..visible_rows.end.min(rows))
+            .flat_map(|row| visible_cols.clone().map(move |col| (row, col)))
+            .filter_map(|offset| by_offset.get(&offset).copied())
+            .collect::<Vec<_>>()
+    });
 
     let on_mouse_down = move |e: MouseEvent| {
         e.prevent_default();
         if e.button() == 2 {
             is_dragging.set(true);
             drag_start.set((e.client_x() as f64, e.client_y() as f64));
-            pan_start.set(pan_offset.get());
+            pan_start.set(camera.get().pan);
         }
     };
 
@@ -129,7 +290,7 @@ fn Grid() -> impl IntoView {
             let dx = e.client_x() as f64 - start_x;
             let dy = e.client_y() as f64 - start_y;
 
-            pan_offset.set((pan_x + dx, pan_y + dy));
+            camera.update(|camera| camera.pan = (pan_x + dx, pan_y + dy));
         }
     };
 
@@ -143,36 +304,108 @@ fn Grid() -> impl IntoView {
         is_dragging.set(false);
     };
 
-    let game_state = use_context::<GameState>().expect("cannot get GameState from context");
-    let tiles = game_state.tiles;
+    let on_wheel = move |e: WheelEvent| {
+        e.prevent_default();
+        let factor = if e.delta_y() < 0. {
+            ZOOM_STEP
+        } else {
+            1. / ZOOM_STEP
+        };
+        let cursor = to_view(e.client_x(), e.client_y());
+        camera.update(|camera| camera.zoom_at(cursor, factor));
+    };
 
-    view! {
-        <svg
-            width="100%"
-            height="100%"
-            class:cursor-grabbing=move || is_dragging.get()
-            on:mousedown=on_mouse_down
-            on:mousemove=on_mouse_move
-            on:mouseup=on_mouse_up
-            on:mouseleave=on_mouse_leave
-            on:contextmenu=move |e: MouseEvent| {
-                e.prevent_default();
+    let on_pointer_down = move |e: PointerEvent| {
+        if e.pointer_type() == "touch" {
+            let position = to_view(e.client_x(), e.client_y());
+            touches.update(|touches| touches.push((e.pointer_id(), position)));
+        }
+    };
+
+    // One finger pans, two pan by their midpoint and zoom by their spread.
+    let on_pointer_move = move |e: PointerEvent| {
+        if e.pointer_type() != "touch" {
+            return;
+        }
+        let position = to_view(e.client_x(), e.client_y());
+        let before = touches.get_untracked();
+        let Some(index) = before.iter().position(|&(id, _)| id == e.pointer_id()) else {
+            return;
+        };
+        let mut after = before.clone();
+        after[index].1 = position;
+        match (before.as_slice(), after.as_slice()) {
+            ([(_, a)], [(_, b)]) => camera.update(|c| c.pan_by(b.0 - a.0, b.1 - a.1)),
+            ([(_, a0), (_, a1), ..], [(_, b0), (_, b1), ..]) => {
+                let middle = |p: &(f64, f64), q: &(f64, f64)| ((p.0 + q.0) / 2., (p.1 + q.1) / 2.);
+                let spread = |p: &(f64, f64), q: &(f64, f64)| (p.0 - q.0).hypot(p.1 - q.1);
+                let (from, to) = (middle(a0, a1), middle(b0, b1));
+                let factor = spread(b0, b1) / spread(a0, a1).max(1.);
+                camera.update(|c| {
+                    c.pan_by(to.0 - from.0, to.1 - from.1);
+                    c.zoom_at(to, factor);
+                });
             }
-        >
-            <g transform=move || {
-                let (x, y) = pan_offset.get();
-                format!("translate({}, {})", x, y)
-            }>
-                {tiles
-                    .into_iter()
-                    .map(|tile| {
-                        view! {
-                            <Tile row=tile.row col=tile.col name=tile.id tile_size is_dragging />
+            _ => {}
+        }
+        touches.set(after);
+    };
+
+    let on_pointer_up = move |e: PointerEvent| {
+        touches.update(|touches| touches.retain(|&(id, _)| id != e.pointer_id()));
+    };
+
+    view! {
+        <div class="relative w-full h-full">
+            <svg
+                node_ref=svg_ref
+                width="100%"
+                height="100%"
+                class="touch-none"
+                class:cursor-grabbing=move || is_dragging.get()
+                on:mousedown=on_mouse_down
+                on:mousemove=on_mouse_move
+                on:mouseup=on_mouse_up
+                on:mouseleave=on_mouse_leave
+                on:wheel=on_wheel
+                on:pointerdown=on_pointer_down
+                on:pointermove=on_pointer_move
+                on:pointerup=on_pointer_up
+                on:pointercancel=on_pointer_up
+                on:contextmenu=move |e: MouseEvent| {
+                    e.prevent_default();
+                }
+            >
+                <g transform=move || {
+                    let Camera { pan: (x, y), zoom } = camera.get();
+                    format!("translate({}, {}) scale({})", x, y, zoom)
+                }>
+                    <For
+                        each=move || visible.get()
+                        key=|tile| tile.id
+                        children=move |tile| {
+                            view! {
+                                <Tile
+                                    row=tile.row
+                                    col=tile.col
+                                    name=tile.id
+                                    terrain=tile.terrain
+                                    tile_size
+                                    is_dragging
+                                />
+                            }
                         }
-                    })
-                    .collect_view()}
-            </g>
-        </svg>
+                    />
+                </g>
+            </svg>
+            <button
+                on:click=move |_| center_on_selected()
+                title="Center on the selected tile (C)"
+                class="absolute top-2 right-2 px-3 py-1 border bg-primary-bg border-primary-border font-bold hover:cursor-pointer hover:bg-highlight-dim/30"
+            >
+                "CENTER"
+            </button>
+        </div>
     }
 }
 
@@ -181,22 +414,18 @@ fn Tile(
     row: u32,
     col: u32,
     name: &'static str,
+    terrain: Terrain,
     tile_size: f64,
     is_dragging: RwSignal<bool>,
 ) -> impl IntoView {
-    let width = tile_size * 2.0;
-    let height = (3.0_f64).sqrt() * tile_size;
-
-    let x = col as f64 * width * 0.75 + tile_size;
-    let offset = if col % 2 == 1 { height / 2. } else { 0. };
-    let y = row as f64 * height + height / 2. + offset;
-
-    let points = points(x + 1., y + 1., tile_size);
+    let (x, y) = Hex::from_offset(row, col).to_pixel(tile_size);
+    let points = points(x, y, tile_size);
 
     let selected_tile =
         use_context::<SelectedTile>().expect("failed to get SelectedTile from context");
 
     let is_selected = move || selected_tile.0.get().eq(name);
+    let fill = terrain.details().fill;
 
     let on_click = move |e: MouseEvent| {
         if !is_dragging.get() && e.button() == 0 {
@@ -208,9 +437,14 @@ fn Tile(
     view! {
         <polygon
             points=points
-            class="stroke-primary-border stroke-2 cursor-pointer"
-            class=(["fill-tertiary-bg", "hover:fill-secondary-bg"], move || !is_selected())
-            class=(["fill-highlight", "hover:fill-highlight-hover"], is_selected)
+            class=move || {
+                let fill = if is_selected() {
+                    "fill-highlight hover:fill-highlight-hover"
+                } else {
+                    fill
+                };
+                format!("stroke-primary-border stroke-2 cursor-pointer hover:opacity-80 {}", fill)
+            }
             on:click=on_click
         />
     }
@@ -0,0 +1,125 @@
+//! What part of the map the tile map shows. Map coordinates are those of
+//! `Hex::to_pixel`, screen coordinates are pixels from the top left corner of
+//! the view; a map point `p` is drawn at `pan + p * zoom`.
+
+use std::ops::Range;
+
+pub const MIN_ZOOM: f64 = 0.1;
+pub const MAX_ZOOM: f64 = 3.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub pan: (f64, f64),
+    pub zoom: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            pan: (0., 0.),
+            zoom: 1.,
+        }
+    }
+}
+
+impl Camera {
+    pub fn to_map(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        ((x - self.pan.0) / self.zoom, (y - self.pan.1) / self.zoom)
+    }
+
+    pub fn pan_by(&mut self, dx: f64, dy: f64) {
+        self.pan = (self.pan.0 + dx, self.pan.1 + dy);
+    }
+
+    /// Scales by `factor`, within `MIN_ZOOM` and `MAX_ZOOM`, keeping the map
+    /// point under `screen` in place.
+    pub fn zoom_at(&mut self, screen: (f64, f64), factor: f64) {
+        let (x, y) = self.to_map(screen);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = (screen.0 - x * self.zoom, screen.1 - y * self.zoom);
+    }
+
+    /// Moves the map point `(x, y)` to the middle of a `viewport` sized view.
+    pub fn center_on(&mut self, (x, y): (f64, f64), viewport: (f64, f64)) {
+        self.pan = (
+            viewport.0 / 2. - x * self.zoom,
+            viewport.1 / 2. - y * self.zoom,
+        );
+    }
+
+    /// Offset rows and columns of the hexes of circumradius `size` that are
+    /// at least partly inside a `viewport` sized view. Never negative, may
+    /// reach past the edge of the map.
+    pub fn visible(&self, viewport: (f64, f64), size: f64) -> (Range<u32>, Range<u32>) {
+        let (left, top) = self.to_map((0., 0.));
+        let (right, bottom) = self.to_map(viewport);
+        let (col_width, row_height) = (size * 1.5, size * 3f64.sqrt());
+
+        // Columns reach `size` either side of their center, rows half a row
+        // above and below it and odd columns sit half a row lower.
+        let cols = ((left - size) / col_width).ceil()..((right + size) / col_width).floor() + 1.;
+        let rows = (top / row_height - 1.).ceil()..(bottom / row_height + 0.5).floor() + 1.;
+        let clamp = |range: Range<f64>| {
+            let start = range.start.max(0.) as u32;
+            start..(range.end.max(0.) as u32).max(start)
+        };
+        (clamp(rows), clamp(cols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::hex::Hex;
+
+    #[test]
+    fn zooming_keeps_the_cursor_over_the_same_point() {
+        let mut camera = Camera {
+            pan: (30., -20.),
+            zoom: 1.,
+        };
+        let cursor = (200., 150.);
+        let before = camera.to_map(cursor);
+        camera.zoom_at(cursor, 2.);
+        assert_eq!(camera.zoom, 2.);
+        assert_eq!(camera.to_map(cursor), before);
+
+        camera.zoom_at(cursor, 100.);
+        assert_eq!(camera.zoom, MAX_ZOOM);
+
+        camera.center_on((10., 10.), (800., 600.));
+        assert_eq!(camera.to_map((400., 300.)), (10., 10.));
+    }
+
+    #[test]
+    fn only_hexes_in_view_are_visible() {
+        let size = 50.;
+        let viewport = (800., 600.);
+        let mut camera = Camera::default();
+        camera.center_on(Hex::from_offset(40, 40).to_pixel(size), viewport);
+        let (rows, cols) = camera.visible(viewport, size);
+        assert!(rows.contains(&40) && cols.contains(&40));
+        assert!(rows.len() < 12 && cols.len() < 14);
+
+        // Every hex overlapping the view is in range.
+        for row in 30..50 {
+            for col in 30..50 {
+                let (x, y) = Hex::from_offset(row, col).to_pixel(size);
+                let (x, y) = (
+                    x * camera.zoom + camera.pan.0,
+                    y * camera.zoom + camera.pan.1,
+                );
+                let overlaps = x + size > 0.
+                    && x - size < viewport.0
+                    && y + size * 0.87 > 0.
+                    && y - size * 0.87 < viewport.1;
+                if overlaps {
+                    assert!(rows.contains(&row) && cols.contains(&col), "{row}, {col}");
+                }
+            }
+        }
+
+        let (rows, cols) = Camera::default().visible(viewport, size);
+        assert_eq!((rows.start, cols.start), (0, 0));
+    }
+}
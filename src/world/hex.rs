@@ -0,0 +1,225 @@
+//! Hex grid geometry. Hexes are flat-topped and addressed by axial
+//! coordinates `(q, r)`, the third cube coordinate being `s = -q - r`. Tiles
+//! store offset coordinates in the "odd-q" layout drawn by the tile map, where
+//! odd columns sit half a hex lower; `Hex::from_offset` and `Hex::to_offset`
+//! convert between the two.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hex {
+    pub q: i32,
+    pub r: i32,
+}
+
+/// Neighbor offsets, clockwise from the one below-right.
+pub const DIRECTIONS: [Hex; 6] = [
+    Hex::new(1, 0),
+    Hex::new(0, 1),
+    Hex::new(-1, 1),
+    Hex::new(-1, 0),
+    Hex::new(0, -1),
+    Hex::new(1, -1),
+];
+
+/// Cheapest route found by `find_path`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    /// Every hex crossed, both ends included.
+    pub hexes: Vec<Hex>,
+    /// Sum of the costs of entering each hex after the first.
+    pub cost: u32,
+}
+
+impl Hex {
+    pub const fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    pub fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    pub fn from_offset(row: u32, col: u32) -> Self {
+        let (row, col) = (row as i32, col as i32);
+        Self::new(col, row - (col - (col & 1)) / 2)
+    }
+
+    /// `(row, col)` in the tile map layout. Negative outside the map.
+    pub fn to_offset(&self) -> (i32, i32) {
+        (self.r + (self.q - (self.q & 1)) / 2, self.q)
+    }
+
+    pub fn neighbors(&self) -> [Hex; 6] {
+        DIRECTIONS.map(|d| Hex::new(self.q + d.q, self.r + d.r))
+    }
+
+    /// Hexes to cross to reach `other`, the largest difference along any axis.
+    pub fn distance(&self, other: Hex) -> u32 {
+        let dq = (self.q - other.q).abs();
+        let dr = (self.r - other.r).abs();
+        let ds = (self.s() - other.s()).abs();
+        dq.max(dr).max(ds) as u32
+    }
+
+    /// Center of the hex for hexes of circumradius `size`, the hex at `(0, 0)`
+    /// being centered on the origin.
+    pub fn to_pixel(&self, size: f64) -> (f64, f64) {
+        let x = size * 1.5 * self.q as f64;
+        let y = size * 3f64.sqrt() * (self.r as f64 + self.q as f64 / 2.);
+        (x, y)
+    }
+
+    /// Hex containing the point `(x, y)`, the inverse of `to_pixel`.
+    pub fn from_pixel(x: f64, y: f64, size: f64) -> Self {
+        let q = x / (size * 1.5);
+        let r = y / (size * 3f64.sqrt()) - q / 2.;
+        Self::round(q, r)
+    }
+
+    /// Hex nearest to fractional axial coordinates.
+    pub fn round(q: f64, r: f64) -> Self {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        // Rounding each axis can break q + r + s = 0, recompute the axis
+        // that moved the most from the other two.
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        Self::new(rq as i32, rr as i32)
+    }
+
+    /// Hexes a straight line to `other` passes through, both ends included.
+    pub fn line_to(&self, other: Hex) -> Vec<Hex> {
+        let n = self.distance(other);
+        // Nudged off the edges so lines along them round the same way both
+        // directions.
+        let (q0, r0) = (self.q as f64 + 1e-6, self.r as f64 + 1e-6);
+        let (q1, r1) = (other.q as f64 + 1e-6, other.r as f64 + 1e-6);
+        (0..=n)
+            .map(|i| {
+                let t = if n == 0 { 0. } else { i as f64 / n as f64 };
+                Hex::round(q0 + (q1 - q0) * t, r0 + (r1 - r0) * t)
+            })
+            .collect()
+    }
+
+    /// Whether `other` can be seen from here, that is no hex strictly between
+    /// the two `blocks`.
+    pub fn can_see(&self, other: Hex, blocks: impl Fn(Hex) -> bool) -> bool {
+        let line = self.line_to(other);
+        line.iter()
+            .skip(1)
+            .take(line.len().saturating_sub(2))
+            .all(|&hex| !blocks(hex))
+    }
+}
+
+/// A* search from `from` to `to`. `cost` is the price of entering a hex,
+/// `None` where it cannot be entered, which must include everything off the
+/// map. Costs below 1 count as 1 to keep the search exact.
+pub fn find_path(from: Hex, to: Hex, cost: impl Fn(Hex) -> Option<u32>) -> Option<Path> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Hex, Hex> = HashMap::new();
+    let mut best: HashMap<Hex, u32> = HashMap::from([(from, 0)]);
+    open.push(Reverse((from.distance(to), 0, from)));
+
+    while let Some(Reverse((_, spent, hex))) = open.pop() {
+        if hex == to {
+            let mut hexes = vec![to];
+            while let Some(&previous) = came_from.get(hexes.last().unwrap()) {
+                hexes.push(previous);
+            }
+            hexes.reverse();
+            return Some(Path { hexes, cost: spent });
+        }
+        if best.get(&hex).is_some_and(|&b| b < spent) {
+            continue;
+        }
+        for next in hex.neighbors() {
+            let Some(step) = cost(next) else {
+                continue;
+            };
+            let spent = spent + step.max(1);
+            if best.get(&next).is_some_and(|&b| b <= spent) {
+                continue;
+            }
+            best.insert(next, spent);
+            came_from.insert(next, hex);
+            open.push(Reverse((spent + next.distance(to), spent, next)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_coordinates_round_trip() {
+        for row in 0..6 {
+            for col in 0..6 {
+                let hex = Hex::from_offset(row, col);
+                assert_eq!(hex.to_offset(), (row as i32, col as i32));
+                let (x, y) = hex.to_pixel(50.);
+                assert_eq!(Hex::from_pixel(x + 10., y - 10., 50.), hex);
+            }
+        }
+        // Odd columns sit half a hex lower.
+        assert_eq!(Hex::from_offset(0, 1).to_pixel(2.).1, 3f64.sqrt());
+    }
+
+    #[test]
+    fn neighbors_are_one_hex_away() {
+        let hex = Hex::from_offset(3, 4);
+        for neighbor in hex.neighbors() {
+            assert_eq!(hex.distance(neighbor), 1);
+        }
+        assert_eq!(Hex::from_offset(0, 0).distance(Hex::from_offset(2, 3)), 4);
+        assert_eq!(Hex::from_offset(0, 0).distance(Hex::from_offset(0, 1)), 1);
+    }
+
+    #[test]
+    fn lines_step_through_adjacent_hexes() {
+        let (a, b) = (Hex::new(0, 0), Hex::new(4, -1));
+        let line = a.line_to(b);
+        assert_eq!(line.len(), 5);
+        assert_eq!((line[0], line[4]), (a, b));
+        assert!(line.windows(2).all(|w| w[0].distance(w[1]) == 1));
+
+        assert!(!a.can_see(b, |hex| line[1..4].contains(&hex)));
+        assert!(a.can_see(b, |hex| hex == a || hex == b));
+    }
+
+    #[test]
+    fn paths_go_around_walls_and_prefer_cheap_ground() {
+        let inside = |hex: Hex| hex.distance(Hex::new(0, 0)) <= 4;
+        let wall = [Hex::new(1, -1), Hex::new(1, 0), Hex::new(1, 1)];
+        let path = find_path(Hex::new(0, 0), Hex::new(2, 0), |hex| {
+            (inside(hex) && !wall.contains(&hex)).then_some(1)
+        })
+        .unwrap();
+        assert_eq!(path.hexes.first(), Some(&Hex::new(0, 0)));
+        assert_eq!(path.hexes.last(), Some(&Hex::new(2, 0)));
+        assert!(path.hexes.iter().all(|hex| !wall.contains(hex)));
+        assert_eq!(path.cost, path.hexes.len() as u32 - 1);
+
+        // Straight across costs 5 + 1, three steps around the swamp 3.
+        let swamp = Hex::new(1, 0);
+        let path = find_path(Hex::new(0, 0), Hex::new(2, 0), |hex| {
+            inside(hex).then_some(if hex == swamp { 5 } else { 1 })
+        })
+        .unwrap();
+        assert_eq!(path.cost, 3);
+
+        let sealed = find_path(Hex::new(0, 0), Hex::new(2, 0), |hex| {
+            (hex.distance(Hex::new(0, 0)) <= 1).then_some(1)
+        });
+        assert_eq!(sealed, None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Procedural world map. Terrain comes from two layers of noise, elevation and
+//! moisture, sampled at the center of every hex; resources, price and
+//! description follow from the terrain. Only integer hashing and plain float
+//! arithmetic are involved, so the server and the browser build the same map
+//! from the same seed.
+
+pub mod camera;
+pub mod hex;
+mod noise;
+
+use std::sync::Mutex;
+
+use leptos::prelude::*;
+
+use crate::entities::tile::TileState;
+use crate::entities::{Money, ResourceKind, ResourceMap, Terrain, Tile};
+use noise::{unit, Noise};
+
+/// Seed of the world every player shares.
+pub const WORLD_SEED: u64 = 0x7472_6164_6577_6f72;
+pub const MAP_WIDTH: u32 = 16;
+pub const MAP_HEIGHT: u32 = 12;
+
+/// Hexes per noise lattice cell, larger values give larger regions.
+const FEATURE_SIZE: f64 = 5.;
+const NOISE_OCTAVES: u32 = 3;
+/// Tile price per percent of resource abundance.
+const PRICE_PER_ABUNDANCE: Money = Money::dollars(4);
+
+/// Lowest and highest abundance in percent, per terrain.
+type AbundanceRanges = [(Terrain, u32, u32); 5];
+
+const RESOURCES: &[(ResourceKind, AbundanceRanges)] = &[
+    (
+        ResourceKind::Water,
+        [
+            (Terrain::Plains, 20, 45),
+            (Terrain::Forest, 30, 55),
+            (Terrain::River, 75, 98),
+            (Terrain::Hills, 10, 30),
+            (Terrain::Mountain, 5, 20),
+        ],
+    ),
+    (
+        ResourceKind::Wood,
+        [
+            (Terrain::Plains, 5, 25),
+            (Terrain::Forest, 60, 95),
+            (Terrain::River, 15, 35),
+            (Terrain::Hills, 15, 40),
+            (Terrain::Mountain, 0, 10),
+        ],
+    ),
+    (
+        ResourceKind::Stone,
+        [
+            (Terrain::Plains, 5, 20),
+            (Terrain::Forest, 5, 20),
+            (Terrain::River, 0, 10),
+            (Terrain::Hills, 35, 65),
+            (Terrain::Mountain, 65, 95),
+        ],
+    ),
+];
+
+/// The fixed part of a generated tile, shared by every `Tile` built from the
+/// same seed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Site {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub resources: ResourceMap,
+    pub price: Money,
+    pub row: u32,
+    pub col: u32,
+    pub terrain: Terrain,
+}
+
+type MapKey = (u64, u32, u32);
+
+/// Maps generated so far. `Tile` borrows its text for `'static`, so every map
+/// is leaked once and reused by every later request for the same seed.
+static MAPS: Mutex<Vec<(MapKey, &'static [Site])>> = Mutex::new(Vec::new());
+
+/// Unowned tiles of the `width` by `height` map generated from `seed`.
+pub fn generate(seed: u64, width: u32, height: u32) -> Vec<Tile<'static>> {
+    sites(seed, width, height)
+        .iter()
+        .map(|site| {
+            let tile_state = TileState::new();
+            tile_state.resources.set(site.resources);
+            Tile {
+                id: site.id,
+                description: site.description,
+                price: site.price,
+                row: site.row,
+                col: site.col,
+                terrain: site.terrain,
+                is_owned: RwSignal::new(false),
+                tile_state,
+            }
+        })
+        .collect()
+}
+
+pub fn sites(seed: u64, width: u32, height: u32) -> &'static [Site] {
+    let key = (seed, width, height);
+    let mut maps = MAPS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(&(_, sites)) = maps.iter().find(|(k, _)| *k == key) {
+        return sites;
+    }
+    let sites: &'static [Site] = Box::leak(build(seed, width, height).into_boxed_slice());
+    maps.push((key, sites));
+    sites
+}
+
+fn build(seed: u64, width: u32, height: u32) -> Vec<Site> {
+    let elevation = Noise::new(seed);
+    let moisture = Noise::new(!seed);
+    let mut sites = Vec::with_capacity((width * height) as usize);
+    for row in 0..height {
+        for col in 0..width {
+            // Hex centers in hex widths, odd columns sit half a hex lower.
+            let x = col as f64 * 0.75;
+            let y = (row as f64 + if col % 2 == 1 { 0.5 } else { 0. }) * 0.75f64.sqrt();
+            let (x, y) = (x / FEATURE_SIZE, y / FEATURE_SIZE);
+            let terrain = classify(
+                elevation.fractal(x, y, NOISE_OCTAVES),
+                moisture.fractal(x, y, NOISE_OCTAVES),
+            );
+            let roll = |salt: u64| unit(seed, &[row as u64, col as u64, salt]);
+            sites.push(site(width, row, col, terrain, roll));
+        }
+    }
+    sites
+}
+
+fn classify(elevation: f64, moisture: f64) -> Terrain {
+    // Roughly a tenth of the map each is mountain, hills and river, the rest
+    // is split between forest and plains.
+    if elevation > 0.69 {
+        Terrain::Mountain
+    } else if elevation > 0.63 {
+        Terrain::Hills
+    } else if elevation < 0.35 {
+        Terrain::River
+    } else if moisture > 0.5 {
+        Terrain::Forest
+    } else {
+        Terrain::Plains
+    }
+}
+
+fn site(width: u32, row: u32, col: u32, terrain: Terrain, roll: impl Fn(u64) -> f64) -> Site {
+    let abundances: Vec<(ResourceKind, u32)> = RESOURCES
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (kind, ranges))| {
+            let &(_, min, max) = ranges.iter().find(|(t, _, _)| *t == terrain)?;
+            Some((
+                *kind,
+                min + ((max - min + 1) as f64 * roll(i as u64)) as u32,
+            ))
+        })
+        .collect();
+    let resources = ResourceMap::new(&abundances);
+
+    let details = terrain.details();
+    let total: u32 = abundances.iter().map(|(_, abundance)| abundance).sum();
+    // Up to 10% either way, so neighbours of the same kind differ a little.
+    let jitter = (roll(100) * 21.) as i64 - 10;
+    let price = (details.base_price + PRICE_PER_ABUNDANCE * total as u64).percent(100 + jitter);
+
+    let opening = details.descriptions
+        [(roll(101) * details.descriptions.len() as f64) as usize % details.descriptions.len()];
+    let description = match resources.present().first() {
+        Some(&(kind, deposit)) if deposit.abundance >= 60 => {
+            format!(
+                "{}, rich in {}.",
+                opening,
+                kind.details().name.to_lowercase()
+            )
+        }
+        Some(&(kind, _)) => format!(
+            "{}, with some {}.",
+            opening,
+            kind.details().name.to_lowercase()
+        ),
+        None => format!("{}.", opening),
+    };
+
+    Site {
+        id: leak(format!("STR-{}", 1001 + row * width + col)),
+        description: leak(description),
+        resources,
+        price,
+        row,
+        col,
+        terrain,
+    }
+}
+
+fn leak(text: String) -> &'static str {
+    Box::leak(text.into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_builds_the_same_map() {
+        assert_eq!(build(42, 12, 9), build(42, 12, 9));
+        assert_ne!(build(42, 12, 9), build(43, 12, 9));
+        assert!(std::ptr::eq(sites(42, 12, 9), sites(42, 12, 9)));
+    }
+
+    #[test]
+    fn every_hex_gets_a_distinct_site() {
+        let sites = build(WORLD_SEED, MAP_WIDTH, MAP_HEIGHT);
+        assert_eq!(sites.len(), (MAP_WIDTH * MAP_HEIGHT) as usize);
+        let mut ids: Vec<_> = sites.iter().map(|site| site.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), sites.len());
+        assert_eq!(sites[1].id, "STR-1002");
+        assert_eq!(
+            (sites[MAP_WIDTH as usize].row, sites[MAP_WIDTH as usize].col),
+            (1, 0)
+        );
+
+        for site in &sites {
+            assert!(site.price > Money::ZERO);
+            assert!(!site.description.is_empty());
+            assert!(!site.resources.present().is_empty());
+        }
+    }
+
+    #[test]
+    fn large_maps_have_every_terrain() {
+        let sites = build(WORLD_SEED, 40, 40);
+        for terrain in Terrain::all() {
+            assert!(
+                sites.iter().any(|site| site.terrain == terrain),
+                "no {:?}",
+                terrain
+            );
+        }
+    }
+}
@@ -0,0 +1,63 @@
+/// SplitMix64 finalizer, mixes well enough to turn coordinates into noise.
+pub fn hash(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Uniform value in [0, 1) derived from `seed` and `parts`.
+pub fn unit(seed: u64, parts: &[u64]) -> f64 {
+    let hashed = parts.iter().fold(hash(seed), |acc, &part| hash(acc ^ part));
+    (hashed >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Value noise: random values on an integer lattice, smoothly interpolated in
+/// between.
+#[derive(Clone, Copy, Debug)]
+pub struct Noise {
+    seed: u64,
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    fn lattice(&self, x: i64, y: i64) -> f64 {
+        unit(self.seed, &[x as u64, y as u64])
+    }
+
+    /// Noise at `(x, y)`, in [0, 1).
+    pub fn value(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = lerp(self.lattice(x0, y0), self.lattice(x0 + 1, y0), tx);
+        let bottom = lerp(self.lattice(x0, y0 + 1), self.lattice(x0 + 1, y0 + 1), tx);
+        lerp(top, bottom, ty)
+    }
+
+    /// Sum of `octaves` layers of noise, each at twice the frequency and half
+    /// the weight of the one before, in [0, 1).
+    pub fn fractal(&self, x: f64, y: f64, octaves: u32) -> f64 {
+        let (mut total, mut weight, mut frequency, mut weights) = (0., 1., 1., 0.);
+        for octave in 0..octaves {
+            let layer = Noise::new(hash(self.seed ^ octave as u64));
+            total += layer.value(x * frequency, y * frequency) * weight;
+            weights += weight;
+            weight /= 2.;
+            frequency *= 2.;
+        }
+        total / weights
+    }
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3. - 2. * t)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}